@@ -5,9 +5,10 @@
 
 mod state;
 
-use hoverwars::{Operation, HoverWarsAbi, Player, Team, MatchState, CrossChainMessage, HoverWarsEvent};
+use hoverwars::{Operation, OperationOutcome, OperationResponse, HoverWarsAbi, HoverWarsError, HoverWarsParameters, ForfeitRecord, GameRoom, HostHandover, JoinAttempt, JoinStatus, LobbyAccess, LobbyVisibility, MatchRules, MatchState, Player, PlayerStanding, QueueEntry, QueueStatus, QueueTicket, Team, READY_CHECK_DURATION_MICROS, REPORT_GRACE_MICROS, SECURITY_LOG_CAPACITY, RejectedMessage, RoomEvent, RoundConfirmation, CrossChainMessage, HoverWarsEvent};
+use hoverwars::rating::SkillRating;
 use linera_sdk::{
-    linera_base_types::{WithContractAbi, StreamName, ChainId},
+    linera_base_types::{WithContractAbi, StreamName, StreamUpdate, ChainId, GenericApplicationId},
     views::{RootView, View},
     Contract, ContractRuntime,
 };
@@ -26,373 +27,1921 @@ impl WithContractAbi for HoverWarsContract {
 }
 
 impl HoverWarsContract {
+    /// Load a room by ID
+    async fn load_room(&self, room_id: &str) -> Option<GameRoom> {
+        self.state.rooms.get(room_id).await.expect("Failed to load room")
+    }
+
+    /// Store a room under its ID
+    fn save_room(&mut self, room: &GameRoom) {
+        self.state.rooms.insert(&room.room_id, room.clone()).expect("Failed to save room");
+    }
+
+    /// Remove a room and its host subscription and state sync records
+    fn remove_room(&mut self, room_id: &str) {
+        self.state.rooms.remove(room_id).expect("Failed to remove room");
+        self.state.subscribed_to_host.remove(room_id).expect("Failed to remove subscription");
+        self.state.pending_state_syncs.remove(room_id).expect("Failed to remove pending state sync");
+    }
+
     /// Subscribe to a player's chain for events
-    fn subscribe_to_player(&mut self, player_chain_id: &str) {
-        if let Some(room) = self.state.room.get() {
-            if let Ok(player_chain) = player_chain_id.parse() {
-                let app_id = self.runtime.application_id().forget_abi();
-                let stream = StreamName::from(format!("game_events_{}", room.room_id));
-                
-                eprintln!("[SUBSCRIPTION] Subscribing to player chain {:?}", player_chain);
-                self.runtime.subscribe_to_events(player_chain, app_id, stream);
-                eprintln!("[SUBSCRIPTION] Subscribed to player events");
+    fn subscribe_to_player(&mut self, room: &GameRoom, player_chain_id: &str) {
+        if let Ok(player_chain) = player_chain_id.parse() {
+            let app_id = self.runtime.application_id().forget_abi();
+            let stream = StreamName::from(room.stream_name());
+
+            eprintln!("[SUBSCRIPTION] Subscribing to player chain {:?}", player_chain);
+            self.runtime.subscribe_to_events(player_chain, app_id, stream);
+            eprintln!("[SUBSCRIPTION] Subscribed to player events");
+        }
+    }
+
+    /// Create a room hosted on this chain
+    fn create_lobby(&mut self, host_name: String, rules: MatchRules, access: LobbyAccess) -> GameRoom {
+        let host_chain_id = self.runtime.chain_id().to_string();
+        let timestamp = self.runtime.system_time().micros().to_string();
+
+        // Room IDs are unique per host chain, and the chain ID makes them unique globally
+        let sequence = *self.state.rooms_created.get();
+        self.state.rooms_created.set(sequence + 1);
+        let room_id = format!("{}-{}", host_chain_id, sequence);
+
+        let room = GameRoom::new(room_id.clone(), host_chain_id.clone(), host_name.clone(), rules, access, timestamp);
+        self.save_room(&room);
+
+        // Host subscribes to self (will subscribe to opponent when they join)
+        self.subscribe_to_player(&room, &host_chain_id);
+
+        // Make the room discoverable
+        self.publish_listing(&room);
+
+        eprintln!("[CREATE_LOBBY] {:?} lobby '{}' created by host '{}' (Blue Team)", room.access.visibility, room_id, host_name);
+        room
+    }
+
+    /// Ask a host chain to let this chain's player into one of its rooms
+    fn request_join(&mut self, host_chain: ChainId, room_id: String, player_name: String, invite_code: Option<String>) {
+        let message = CrossChainMessage::JoinRequest {
+            room_id: room_id.clone(),
+            player_chain_id: self.runtime.chain_id(),
+            player_name,
+            invite_code,
+        };
+
+        // Tracked so the request bounces back if the host chain cannot handle it
+        self.runtime.prepare_message(message).with_tracking().send_to(host_chain);
+
+        let attempt = JoinAttempt {
+            room_id: room_id.clone(),
+            host_chain_id: host_chain.to_string(),
+            status: JoinStatus::Pending,
+            rejection_reason: None,
+            requested_at: self.runtime.system_time().micros().to_string(),
+        };
+        self.state.join_attempts.insert(&room_id, attempt).expect("Failed to save join attempt");
+
+        eprintln!("[JOIN_LOBBY] Join request sent to chain {}", host_chain);
+    }
+
+    /// Publish a hosted public room's current listing to the lobby directory, if one is configured
+    fn publish_listing(&mut self, room: &GameRoom) {
+        if room.host_chain_id != self.runtime.chain_id().to_string() || room.access.visibility == LobbyVisibility::Private {
+            return;
+        }
+        if let Some(directory_chain) = self.runtime.application_parameters().directory_chain_id {
+            let message = CrossChainMessage::RegisterLobby {
+                listing: room.to_listing(),
+            };
+            self.runtime.send_message(directory_chain, message);
+            eprintln!("[DIRECTORY] Published room '{}' ({:?}) to directory chain {}", room.room_id, room.match_state, directory_chain);
+        }
+    }
+
+    /// Remove a hosted room from the lobby directory, if one is configured
+    fn unpublish_listing(&mut self, room: &GameRoom) {
+        if room.host_chain_id != self.runtime.chain_id().to_string() {
+            return;
+        }
+        if let Some(directory_chain) = self.runtime.application_parameters().directory_chain_id {
+            let message = CrossChainMessage::UnregisterLobby {
+                room_id: room.room_id.clone(),
+            };
+            self.runtime.send_message(directory_chain, message);
+            eprintln!("[DIRECTORY] Removed room '{}' from directory chain {}", room.room_id, directory_chain);
+        }
+    }
+
+    /// Check that the message being executed was sent by the expected chain.
+    /// Mismatches are recorded in the security log.
+    fn authenticate_origin(&mut self, message_kind: &str, room_id: &str, expected_chain_id: &str) -> bool {
+        let origin = self.runtime.message_origin_chain_id().map(|chain| chain.to_string());
+        if origin.as_deref() == Some(expected_chain_id) {
+            return true;
+        }
+
+        self.log_rejected_message(message_kind, room_id, origin, expected_chain_id);
+        false
+    }
+
+    /// Identify the room member that sent the message being executed.
+    /// Messages from chains outside the room are recorded in the security log.
+    fn authenticate_member(&mut self, message_kind: &str, room: &GameRoom) -> Option<Player> {
+        let origin = self.runtime.message_origin_chain_id().map(|chain| chain.to_string());
+        if let Some(player) = origin.as_deref().and_then(|chain| room.player(chain)) {
+            return Some(player.clone());
+        }
+
+        self.log_rejected_message(message_kind, &room.room_id, origin, "room member");
+        None
+    }
+
+    /// Check that this chain hosts the room a host-bound message is for.
+    /// Guest copies only change through the host's events, so messages reaching them are
+    /// recorded in the security log.
+    fn authenticate_host_chain(&mut self, message_kind: &str, room: &GameRoom) -> bool {
+        if room.host_chain_id == self.runtime.chain_id().to_string() {
+            return true;
+        }
+
+        let origin = self.runtime.message_origin_chain_id().map(|chain| chain.to_string());
+        let expected = format!("message to host {}", room.host_chain_id);
+        self.log_rejected_message(message_kind, &room.room_id, origin, &expected);
+        false
+    }
+
+    fn log_rejected_message(&mut self, message_kind: &str, room_id: &str, origin: Option<String>, expected: &str) {
+        eprintln!("[SECURITY] Rejected {} for room '{}' from {:?} (expected {})", message_kind, room_id, origin, expected);
+        let timestamp = self.runtime.system_time().micros().to_string();
+        let entry_id = *self.state.security_log_count.get();
+        self.state.security_log_count.set(entry_id.saturating_add(1));
+        let entry = RejectedMessage {
+            entry_id,
+            message_kind: message_kind.to_string(),
+            room_id: room_id.to_string(),
+            origin_chain_id: origin,
+            expected_chain_id: expected.to_string(),
+            timestamp,
+        };
+        self.state.security_log.insert(&entry_id, entry).expect("Failed to save rejected message");
+
+        // Any chain can trigger rejections, so only the most recent ones are kept
+        if let Some(dropped) = entry_id.checked_sub(SECURITY_LOG_CAPACITY) {
+            self.state.security_log.remove(&dropped).expect("Failed to drop rejected message");
+        }
+    }
+
+    /// Record the outcome of a join request sent from this chain
+    async fn update_join_attempt(&mut self, room_id: &str, status: JoinStatus, rejection_reason: Option<String>) {
+        let attempt = self.state.join_attempts.get_mut(room_id)
+            .await
+            .expect("Failed to load join attempt");
+
+        match attempt {
+            Some(attempt) => {
+                attempt.status = status;
+                attempt.rejection_reason = rejection_reason;
+            }
+            None => eprintln!("[JOIN_STATUS] No join request pending for room '{}'", room_id),
+        }
+    }
+
+    /// Whether this chain keeps the public lobby directory
+    fn is_directory_chain(&mut self) -> bool {
+        self.runtime.application_parameters().directory_chain_id == Some(self.runtime.chain_id())
+    }
+
+    /// Whether this chain runs the matchmaking queue
+    fn is_matchmaking_chain(&mut self) -> bool {
+        self.runtime.application_parameters().matchmaking_chain_id == Some(self.runtime.chain_id())
+    }
+
+    /// Check that the message being executed was sent by the matchmaking chain
+    fn authenticate_matchmaker(&mut self, message_kind: &str) -> bool {
+        let Some(matchmaking_chain) = self.runtime.application_parameters().matchmaking_chain_id else {
+            eprintln!("[SECURITY] Rejected {}: no matchmaking chain is configured", message_kind);
+            return false;
+        };
+        self.authenticate_origin(message_kind, "", &matchmaking_chain.to_string())
+    }
+
+    /// Put a player in the queue, then pair every waiting player who has a compatible opponent.
+    /// Rating windows widen with waiting time, so all waiting players are checked against each
+    /// other again whenever a player enters the queue.
+    async fn enqueue(&mut self, entry: QueueEntry) {
+        eprintln!("[MATCHMAKING] {} waiting in '{}' queue at rating {}", entry.chain_id, entry.mode, entry.rating);
+        self.state.matchmaking_queue
+            .insert(&entry.chain_id.clone(), entry)
+            .expect("Failed to save queue entry");
+
+        let now = self.runtime.system_time().micros();
+        let mut waiting: Vec<QueueEntry> = self.state.matchmaking_queue.index_values().await
+            .expect("Failed to load matchmaking queue")
+            .into_iter()
+            .map(|(_, waiting)| waiting)
+            .collect();
+
+        // The longest-waiting player picks the closest-rated compatible opponent first
+        waiting.sort_by(|a, b| a.entered_at_micros.cmp(&b.entered_at_micros).then_with(|| a.chain_id.cmp(&b.chain_id)));
+        while !waiting.is_empty() {
+            let host = waiting.remove(0);
+            let opponent = waiting.iter()
+                .enumerate()
+                .filter(|(_, candidate)| host.accepts(candidate, now))
+                .min_by_key(|(_, candidate)| (candidate.rating.abs_diff(host.rating), candidate.entered_at_micros))
+                .map(|(index, _)| index);
+
+            if let Some(index) = opponent {
+                let opponent = waiting.remove(index);
+                self.pair(host, opponent);
+            }
+        }
+    }
+
+    /// Ask the player who waited longer to host a lobby for the paired opponent
+    fn pair(&mut self, host: QueueEntry, opponent: QueueEntry) {
+        self.state.matchmaking_queue.remove(&host.chain_id).expect("Failed to remove queue entry");
+        self.state.matchmaking_queue.remove(&opponent.chain_id).expect("Failed to remove queue entry");
+        let Ok(host_chain) = host.chain_id.parse::<ChainId>() else {
+            return;
+        };
+        eprintln!("[MATCHMAKING] Paired {} ({}) with {} ({})", host.chain_id, host.rating, opponent.chain_id, opponent.rating);
+
+        self.runtime.send_message(host_chain, CrossChainMessage::CreateMatchLobby {
+            opponent_chain_id: opponent.chain_id.clone(),
+        });
+        self.state.pending_matches
+            .insert(&host.chain_id, opponent)
+            .expect("Failed to save pending match");
+    }
+
+    /// Whether this chain keeps the global leaderboard
+    fn is_leaderboard_chain(&mut self) -> bool {
+        self.runtime.application_parameters().leaderboard_chain_id == Some(self.runtime.chain_id())
+    }
+
+    /// Emit an event on a room's stream, advancing the room version
+    fn emit_room_event(&mut self, room: &mut GameRoom, event: HoverWarsEvent) {
+        room.room_version += 1;
+        let room_event = RoomEvent {
+            room_version: room.room_version,
+            event,
+        };
+        self.runtime.emit(room.stream_name().into(), &room_event);
+    }
+
+    /// Ask the host for the full room state after missing events (once per gap)
+    async fn request_state_sync(&mut self, room: &GameRoom) {
+        let pending = self.state.pending_state_syncs.get(&room.room_id).await.expect("Failed to load pending state sync");
+        if pending.is_some() {
+            return;
+        }
+        let Ok(host_chain) = room.host_chain_id.parse::<ChainId>() else {
+            return;
+        };
+
+        eprintln!("[STATE_SYNC] Room '{}' is behind at version {}, requesting full state", room.room_id, room.room_version);
+        let message = CrossChainMessage::RequestStateSync {
+            room_id: room.room_id.clone(),
+            known_version: room.room_version,
+        };
+        self.runtime.send_message(host_chain, message);
+        self.state.pending_state_syncs
+            .insert(&room.room_id, room.room_version)
+            .expect("Failed to save pending state sync");
+    }
+
+    /// Start (or restart) the ready check of a hosted room
+    fn begin_ready_check(&mut self, room: &mut GameRoom) {
+        let now = self.runtime.system_time().micros();
+        let deadline_micros = now + READY_CHECK_DURATION_MICROS;
+        room.begin_ready_check(deadline_micros);
+
+        self.emit_room_event(
+            room,
+            HoverWarsEvent::ReadyCheckStarted {
+                deadline_micros,
+                timestamp: now.to_string(),
+            }
+        );
+    }
+
+    /// Record a finished match in this chain's match archive and player profile
+    fn record_match_end(&mut self, room: &GameRoom, finished_at: String) {
+        let match_id = self.state.match_archive.count() as u32;
+        let archived = room.archive(match_id, finished_at);
+        eprintln!("[ARCHIVE] Room '{}' match archived as #{}", room.room_id, match_id);
+
+        let current_chain = self.runtime.chain_id().to_string();
+        if let Some(player) = room.player(&current_chain) {
+            self.state.profile.get_mut().record_match(&archived, player);
+
+            // The host reports the result to the leaderboard
+            if let Some(leaderboard_chain) = self.runtime.application_parameters().leaderboard_chain_id.filter(|_| room.host_chain_id == current_chain) {
+                let message = CrossChainMessage::MatchResult {
+                    archived_match: archived.clone(),
+                };
+                self.runtime.send_message(leaderboard_chain, message);
+                eprintln!("[LEADERBOARD] Reported room '{}' result to leaderboard chain {}", room.room_id, leaderboard_chain);
+            }
+        }
+
+        self.state.match_archive.push(archived);
+    }
+
+    /// Announce the end of a hosted room's match and record it
+    fn end_match(&mut self, room: &mut GameRoom, timestamp: String) {
+        let winner = room.winner;
+        let blue_score = room.get_blue_score();
+        let red_score = room.get_red_score();
+        let forfeit = room.forfeit.clone();
+        self.emit_room_event(
+            room,
+            HoverWarsEvent::MatchEnded {
+                winner,
+                blue_score,
+                red_score,
+                timestamp: timestamp.clone(),
+                forfeit,
+            }
+        );
+        self.record_match_end(room, timestamp);
+    }
+
+    /// End a hosted room's match in play with the forfeiting team losing
+    fn forfeit_match(&mut self, mut room: GameRoom, forfeit: ForfeitRecord) -> GameRoom {
+        let team = forfeit.team;
+        let reason = forfeit.reason.clone();
+
+        if room.forfeit_match(forfeit) {
+            let timestamp = self.runtime.system_time().micros().to_string();
+            self.end_match(&mut room, timestamp);
+            self.unpublish_listing(&room);
+            eprintln!("[FORFEIT] {:?} forfeited room '{}' ({}). Winner: {:?}", team, room.room_id, reason, room.winner);
+        }
+
+        self.save_room(&room);
+        room
+    }
+
+    /// Give up a hosted room's match on behalf of a player's team
+    fn concede(&mut self, room: GameRoom, player: &Player, reason: String) -> GameRoom {
+        let forfeit = ForfeitRecord {
+            team: player.team,
+            player_chain_id: player.chain_id.clone(),
+            reason,
+            round_number: room.current_round,
+        };
+        self.forfeit_match(room, forfeit)
+    }
+
+    /// Award a hosted room's match to a player's team after the other team missed the report deadline
+    fn claim_timeout_win(&mut self, room: GameRoom, player: &Player) -> Result<GameRoom, HoverWarsError> {
+        let now = self.runtime.system_time().micros();
+        room.check_timeout_claim(player.team, now)?;
+
+        let forfeit = ForfeitRecord {
+            team: player.team.opponent(),
+            player_chain_id: player.chain_id.clone(),
+            reason: "missed the report deadline".to_string(),
+            round_number: room.current_round,
+        };
+        Ok(self.forfeit_match(room, forfeit))
+    }
+
+    /// Start a match in a hosted room and announce it
+    fn start_match(&mut self, room: &mut GameRoom, timestamp: String) {
+        room.start_match(timestamp.clone());
+
+        let blue_team = room.blue_team.iter().map(|p| p.name.clone()).collect();
+        let red_team = room.red_team.iter().map(|p| p.name.clone()).collect();
+        self.emit_room_event(
+            room,
+            HoverWarsEvent::MatchStarted {
+                blue_team,
+                red_team,
+                timestamp,
+            }
+        );
+        self.publish_listing(room);
+    }
+
+    /// Record a rematch request on the host chain and start the rematch once everyone agreed
+    fn request_rematch(&mut self, mut room: GameRoom, player_chain_id: &str, swap_sides: bool) -> GameRoom {
+        let timestamp = self.runtime.system_time().micros().to_string();
+        room.request_rematch(player_chain_id, swap_sides);
+        self.emit_room_event(
+            &mut room,
+            HoverWarsEvent::RematchRequested {
+                requested_by: player_chain_id.to_string(),
+                swap_sides,
+                timestamp,
+            }
+        );
+        eprintln!("[REMATCH] {} requested a rematch in room '{}' (swap sides: {})", player_chain_id, room.room_id, swap_sides);
+
+        self.start_rematch_if_agreed(room)
+    }
+
+    /// Record a rematch acceptance on the host chain and start the rematch once everyone agreed
+    fn accept_rematch(&mut self, mut room: GameRoom, player_chain_id: &str) -> GameRoom {
+        let timestamp = self.runtime.system_time().micros().to_string();
+        room.accept_rematch(player_chain_id);
+        self.emit_room_event(
+            &mut room,
+            HoverWarsEvent::RematchAccepted {
+                player_chain_id: player_chain_id.to_string(),
+                timestamp,
+            }
+        );
+        eprintln!("[REMATCH] {} accepted the rematch in room '{}'", player_chain_id, room.room_id);
+
+        self.start_rematch_if_agreed(room)
+    }
+
+    /// Ready the same players up for a new match once all of them agreed to the rematch
+    fn start_rematch_if_agreed(&mut self, mut room: GameRoom) -> GameRoom {
+        if room.rematch_agreed() {
+            let timestamp = self.runtime.system_time().micros().to_string();
+            let swap_sides = room.rematch.as_ref().is_some_and(|rematch| rematch.swap_sides);
+
+            if swap_sides {
+                room.swap_sides();
+            }
+            room.rematch = None;
+
+            self.emit_room_event(
+                &mut room,
+                HoverWarsEvent::RematchStarted {
+                    swap_sides,
+                    timestamp,
+                }
+            );
+            // The rematch starts like any match: once every player is ready
+            self.begin_ready_check(&mut room);
+            self.publish_listing(&room);
+            eprintln!("[REMATCH] Rematch agreed in room '{}'. Ready check started", room.room_id);
+        }
+
+        self.save_room(&room);
+        room
+    }
+
+    /// Record a player's readiness on the host chain and start the match once everyone is ready
+    fn update_readiness(&mut self, mut room: GameRoom, player_chain_id: &str, ready: bool) -> GameRoom {
+        let now = self.runtime.system_time().micros();
+
+        // Readiness only counts within one ready check window
+        if room.ready_check_expired(now) {
+            eprintln!("[READY_CHECK] Room '{}' ready check expired, restarting", room.room_id);
+            self.begin_ready_check(&mut room);
+        }
+
+        room.set_ready(player_chain_id, ready);
+        self.emit_room_event(
+            &mut room,
+            HoverWarsEvent::PlayerReady {
+                player_chain_id: player_chain_id.to_string(),
+                ready,
+                timestamp: now.to_string(),
+            }
+        );
+        eprintln!("[READY_CHECK] Room '{}': {} is {}", room.room_id, player_chain_id, if ready { "ready" } else { "not ready" });
+
+        if room.all_ready() {
+            self.start_match(&mut room, now.to_string());
+            eprintln!("[READY_CHECK] Everyone is ready in room '{}'. Match started!", room.room_id);
+        }
+
+        self.save_room(&room);
+        room
+    }
+
+    /// Apply the outcome of a round report on the host chain, publish it to the room's stream
+    /// and return the updated room
+    fn resolve_round_reports(&mut self, mut room: GameRoom, confirmation: RoundConfirmation) -> GameRoom {
+        let round_number = room.current_round;
+        let now = self.runtime.system_time().micros();
+        let timestamp = now.to_string();
+
+        match confirmation {
+            RoundConfirmation::Pending => {
+                // The silent team's grace period starts with the first report
+                let team = if room.has_reported(Team::Blue) { Team::Blue } else { Team::Red };
+                let deadline_micros = now + REPORT_GRACE_MICROS;
+                room.report_deadline_micros = Some(deadline_micros);
+                self.emit_room_event(
+                    &mut room,
+                    HoverWarsEvent::RoundReported {
+                        round_number,
+                        team,
+                        deadline_micros,
+                        timestamp,
+                    }
+                );
+
+                eprintln!("[ROUND_REPORTS] Room '{}' round {} waiting for the other report", room.room_id, round_number);
+            }
+
+            RoundConfirmation::Disputed { blue_result, red_result } => {
+                self.emit_room_event(
+                    &mut room,
+                    HoverWarsEvent::RoundDisputed {
+                        round_number,
+                        blue_result,
+                        red_result,
+                        timestamp,
+                    }
+                );
+
+                eprintln!("[ROUND_REPORTS] Room '{}' round {} disputed: Blue says {:?}, Red says {:?}",
+                         room.room_id, round_number, blue_result, red_result);
+            }
+
+            RoundConfirmation::Confirmed { result, stats } => {
+                let overtime_round = room.overtime;
+                let match_ended = room.apply_round_result(result, stats.clone(), timestamp.clone());
+
+                let blue_score = room.get_blue_score();
+                let red_score = room.get_red_score();
+
+                // Emit round completed event
+                self.emit_room_event(
+                    &mut room,
+                    HoverWarsEvent::RoundCompleted {
+                        round_number,
+                        result,
+                        blue_score,
+                        red_score,
+                        timestamp: timestamp.clone(),
+                        overtime: overtime_round,
+                        stats,
+                    }
+                );
+
+                eprintln!("[ROUND_REPORTS] Room '{}' round {} confirmed: {:?}. Blue: {}, Red: {}",
+                         room.room_id, round_number, result, blue_score, red_score);
+
+                if match_ended {
+                    self.end_match(&mut room, timestamp);
+                    eprintln!("[ROUND_REPORTS] Match ended! Winner: {:?}", room.winner);
+
+                    // Finished rooms are no longer listed
+                    self.unpublish_listing(&room);
+                } else if room.overtime && !overtime_round {
+                    self.emit_room_event(
+                        &mut room,
+                        HoverWarsEvent::OvertimeStarted {
+                            blue_score,
+                            red_score,
+                            timestamp,
+                        }
+                    );
+                    eprintln!("[ROUND_REPORTS] Room '{}' level at {}-{}, going to sudden death", room.room_id, blue_score, red_score);
+                }
+            }
+        }
+
+        self.save_room(&room);
+        room
+    }
+
+    /// Unsubscribe from a chain's events for a room
+    fn unsubscribe_from_chain(&mut self, room: &GameRoom, chain: ChainId) {
+        let app_id = self.runtime.application_id().forget_abi();
+        let stream = StreamName::from(room.stream_name());
+        self.runtime.unsubscribe_from_events(chain, app_id, stream);
+    }
+
+    /// Execute an operation on this chain
+    async fn run_operation(&mut self, operation: Operation) -> Result<OperationResponse, HoverWarsError> {
+        match operation {
+            Operation::CreateLobby { host_name, rules, access } => {
+                let rules = rules.unwrap_or_else(|| self.runtime.application_parameters().default_rules);
+                if let Err(error) = rules.validate().and_then(|_| access.validate()) {
+                    eprintln!("[CREATE_LOBBY] ERROR: {}", error);
+                    return Err(error);
+                }
+
+                let room = self.create_lobby(host_name, rules, access);
+                Ok(OperationResponse::for_room(&room))
+            }
+
+            Operation::JoinLobby { host_chain_id, room_id, player_name, invite_code } => {
+                eprintln!("[JOIN_LOBBY] Sending join request for room '{}' to host chain '{}' from player '{}'", room_id, host_chain_id, player_name);
+
+                let Ok(target_chain) = host_chain_id.parse::<ChainId>() else {
+                    eprintln!("[JOIN_LOBBY] Invalid host_chain_id format: {}", host_chain_id);
+                    return Err(HoverWarsError::InvalidChainId(host_chain_id));
+                };
+
+                self.request_join(target_chain, room_id.clone(), player_name, invite_code);
+                Ok(OperationResponse::for_room_id(room_id))
+            }
+
+            Operation::ReportRoundResult { room_id, round_number, result, stats } => {
+                let Some(mut room) = self.load_room(&room_id).await else {
+                    eprintln!("[REPORT_ROUND] ERROR: Room '{}' not found", room_id);
+                    return Err(HoverWarsError::RoomNotFound(room_id));
+                };
+                let current_chain = self.runtime.chain_id().to_string();
+
+                // Each team reports from its players' chains; the round only counts once both teams agree
+                let Some(team) = room.team_of(&current_chain) else {
+                    eprintln!("[REPORT_ROUND] ERROR: Caller is not a player in this room");
+                    return Err(HoverWarsError::NotInRoom);
+                };
+
+                if !room.accepts_round_reports() {
+                    eprintln!("[REPORT_ROUND] ERROR: Match is not in progress");
+                    return Err(HoverWarsError::MatchNotInProgress);
+                }
+
+                // Retried reports must not count twice
+                if room.has_round(round_number) {
+                    eprintln!("[REPORT_ROUND] ERROR: Round {} is already recorded", round_number);
+                    return Err(HoverWarsError::AlreadyReported(round_number));
+                }
+
+                if round_number != room.current_round {
+                    eprintln!("[REPORT_ROUND] ERROR: Report is for round {}, current round is {}", round_number, room.current_round);
+                    return Err(HoverWarsError::WrongRound { reported: round_number, current: room.current_round });
+                }
+
+                if room.has_reported(team) {
+                    eprintln!("[REPORT_ROUND] ERROR: {:?} already reported round {}", team, round_number);
+                    return Err(HoverWarsError::AlreadyReported(round_number));
+                }
+
+                if let Err(error) = stats.validate(&room, team, result) {
+                    eprintln!("[REPORT_ROUND] ERROR: {}", error);
+                    return Err(error);
+                }
+
+                let confirmation = room.submit_round_report(team, result, stats.clone());
+                eprintln!("[REPORT_ROUND] Room '{}' round {}: {:?} reported {:?}", room_id, round_number, team, result);
+
+                if room.host_chain_id == current_chain {
+                    // Host holds the authoritative room and resolves the round
+                    let room = self.resolve_round_reports(room, confirmation);
+                    Ok(OperationResponse::for_room(&room))
+                } else {
+                    // Guest proposes its result to the host
+                    self.save_room(&room);
+
+                    let Ok(host_chain) = room.host_chain_id.parse::<ChainId>() else {
+                        return Err(HoverWarsError::InvalidChainId(room.host_chain_id));
+                    };
+                    let message = CrossChainMessage::RoundReport {
+                        room_id,
+                        round_number,
+                        result,
+                        stats,
+                    };
+                    self.runtime.send_message(host_chain, message);
+                    Ok(OperationResponse::for_room(&room))
+                }
+            }
+
+            Operation::LeaveLobby { room_id, handover } => {
+                let Some(mut room) = self.load_room(&room_id).await else {
+                    eprintln!("[LEAVE_LOBBY] ERROR: Room '{}' not found", room_id);
+                    return Err(HoverWarsError::RoomNotFound(room_id));
+                };
+                let current_chain = self.runtime.chain_id();
+                let timestamp = self.runtime.system_time().micros().to_string();
+
+                let is_host = room.host_chain_id == current_chain.to_string();
+                let new_host = room.guests()
+                    .next()
+                    .map(|p| p.chain_id.clone())
+                    .filter(|_| is_host && handover != HostHandover::CloseRoom);
+
+                if let Some(new_host_chain_id) = new_host {
+                    // Host leaving - hand the room over to the first remaining player
+                    eprintln!("[LEAVE_LOBBY] Host leaving, handing room '{}' over to {}", room_id, new_host_chain_id);
+
+                    // A match in play only goes on under the new host if the host's team keeps a player
+                    let host_forfeits = handover == HostHandover::Forfeit
+                        || (room.accepts_round_reports() && !room.can_continue_without(&room.host_chain_id));
+                    if host_forfeits {
+                        let forfeit = ForfeitRecord {
+                            team: room.team_of(&room.host_chain_id).unwrap_or_default(),
+                            player_chain_id: room.host_chain_id.clone(),
+                            reason: "host left the room".to_string(),
+                            round_number: room.current_round,
+                        };
+                        if room.forfeit_match(forfeit) {
+                            self.end_match(&mut room, timestamp.clone());
+                            eprintln!("[LEAVE_LOBBY] Match forfeited to {:?}", room.winner);
+                        }
+                    }
+
+                    let guest_chains: Vec<ChainId> = room.guests().filter_map(|p| p.chain_id.parse().ok()).collect();
+                    for guest_chain in guest_chains {
+                        let message = CrossChainMessage::HostTransfer {
+                            room_data: room.clone(),
+                            new_host_chain_id: new_host_chain_id.clone(),
+                        };
+                        self.runtime.send_message(guest_chain, message);
+
+                        // Unsubscribe from guest
+                        self.unsubscribe_from_chain(&room, guest_chain);
+                    }
+
+                    // The new host lists the room under its own chain
+                    self.unpublish_listing(&room);
+                    self.remove_room(&room_id);
+
+                } else if is_host {
+                    // Host leaving - delete room and notify every guest
+                    eprintln!("[LEAVE_LOBBY] Host leaving, deleting room '{}'", room_id);
+
+                    let guest_chains: Vec<ChainId> = room.guests().filter_map(|p| p.chain_id.parse().ok()).collect();
+                    for guest_chain in guest_chains {
+                        let message = CrossChainMessage::RoomDeleted {
+                            room_id: room_id.clone(),
+                            timestamp: timestamp.clone(),
+                        };
+                        self.runtime.send_message(guest_chain, message);
+
+                        // Unsubscribe from guest
+                        self.unsubscribe_from_chain(&room, guest_chain);
+                    }
+
+                    // Clear room
+                    self.unpublish_listing(&room);
+                    self.remove_room(&room_id);
+
+                } else {
+                    // Guest leaving - notify host
+                    eprintln!("[LEAVE_LOBBY] Guest leaving room '{}'", room_id);
+
+                    if let Ok(host_chain) = room.host_chain_id.parse::<ChainId>() {
+                        let message = CrossChainMessage::PlayerLeftNotification {
+                            room_id: room_id.clone(),
+                            player_chain_id: current_chain.to_string(),
+                            timestamp: timestamp.clone(),
+                        };
+                        self.runtime.send_message(host_chain, message);
+
+                        // Unsubscribe from host
+                        self.unsubscribe_from_chain(&room, host_chain);
+                    }
+
+                    // Clear local state
+                    self.remove_room(&room_id);
+                }
+
+                Ok(OperationResponse::for_room_id(room_id))
+            }
+
+            Operation::SetReady { room_id, ready } => {
+                let Some(mut room) = self.load_room(&room_id).await else {
+                    eprintln!("[SET_READY] ERROR: Room '{}' not found", room_id);
+                    return Err(HoverWarsError::RoomNotFound(room_id));
+                };
+                let current_chain = self.runtime.chain_id().to_string();
+
+                if room.player(&current_chain).is_none() {
+                    eprintln!("[SET_READY] ERROR: Caller is not a player in this room");
+                    return Err(HoverWarsError::NotInRoom);
+                }
+
+                if room.match_state != MatchState::ReadyCheck {
+                    eprintln!("[SET_READY] ERROR: Room '{}' is not in a ready check", room_id);
+                    return Err(HoverWarsError::NotInReadyCheck);
+                }
+
+                if room.host_chain_id == current_chain {
+                    let room = self.update_readiness(room, &current_chain, ready);
+                    Ok(OperationResponse::for_room(&room))
+                } else {
+                    // Guest records its readiness and tells the host
+                    room.set_ready(&current_chain, ready);
+                    self.save_room(&room);
+
+                    let Ok(host_chain) = room.host_chain_id.parse::<ChainId>() else {
+                        return Err(HoverWarsError::InvalidChainId(room.host_chain_id));
+                    };
+                    self.runtime.send_message(host_chain, CrossChainMessage::ReadyUpdate { room_id, ready });
+                    Ok(OperationResponse::for_room(&room))
+                }
+            }
+
+            Operation::SetProfile { display_name } => {
+                if let Err(error) = self.state.profile.get_mut().set_display_name(&display_name) {
+                    eprintln!("[SET_PROFILE] ERROR: {}", error);
+                    return Err(error);
+                }
+
+                eprintln!("[SET_PROFILE] Display name set to '{}'", self.state.profile.get().display_name);
+                Ok(OperationResponse::without_room())
+            }
+
+            Operation::EnterQueue { mode, rating, player_name } => {
+                let Some(matchmaking_chain) = self.runtime.application_parameters().matchmaking_chain_id else {
+                    eprintln!("[ENTER_QUEUE] ERROR: No matchmaking chain is configured");
+                    return Err(HoverWarsError::MatchmakingDisabled);
+                };
+
+                if self.state.queue_ticket.get().as_ref().is_some_and(|ticket| ticket.status == QueueStatus::Queued) {
+                    eprintln!("[ENTER_QUEUE] ERROR: Already in the matchmaking queue");
+                    return Err(HoverWarsError::AlreadyQueued);
+                }
+
+                let message = CrossChainMessage::EnterQueue {
+                    mode: mode.clone(),
+                    rating,
+                    player_name: player_name.clone(),
+                };
+                self.runtime.send_message(matchmaking_chain, message);
+
+                self.state.queue_ticket.set(Some(QueueTicket {
+                    mode: mode.clone(),
+                    rating,
+                    player_name,
+                    status: QueueStatus::Queued,
+                    room_id: None,
+                    entered_at: self.runtime.system_time().micros().to_string(),
+                }));
+                eprintln!("[ENTER_QUEUE] Entered '{}' queue at rating {}", mode, rating);
+                Ok(OperationResponse::without_room())
+            }
+
+            Operation::LeaveQueue => {
+                let Some(matchmaking_chain) = self.runtime.application_parameters().matchmaking_chain_id else {
+                    eprintln!("[LEAVE_QUEUE] ERROR: No matchmaking chain is configured");
+                    return Err(HoverWarsError::MatchmakingDisabled);
+                };
+
+                if !self.state.queue_ticket.get().as_ref().is_some_and(|ticket| ticket.status == QueueStatus::Queued) {
+                    eprintln!("[LEAVE_QUEUE] ERROR: Not in the matchmaking queue");
+                    return Err(HoverWarsError::NotQueued);
+                }
+
+                self.runtime.send_message(matchmaking_chain, CrossChainMessage::LeaveQueue);
+                self.state.queue_ticket.set(None);
+                eprintln!("[LEAVE_QUEUE] Left the matchmaking queue");
+                Ok(OperationResponse::without_room())
+            }
+
+            Operation::KickPlayer { room_id, chain_id, ban } => {
+                let Some(mut room) = self.load_room(&room_id).await else {
+                    eprintln!("[KICK_PLAYER] ERROR: Room '{}' not found", room_id);
+                    return Err(HoverWarsError::RoomNotFound(room_id));
+                };
+
+                if room.host_chain_id != self.runtime.chain_id().to_string() {
+                    eprintln!("[KICK_PLAYER] ERROR: Only the host can kick players");
+                    return Err(HoverWarsError::NotHost);
+                }
+
+                // The host cannot kick itself; it leaves the lobby instead
+                if chain_id == room.host_chain_id || room.player(&chain_id).is_none() {
+                    eprintln!("[KICK_PLAYER] ERROR: {} is not a guest in room '{}'", chain_id, room_id);
+                    return Err(HoverWarsError::PlayerNotFound(chain_id));
+                }
+                // Kicking resets the room, which would erase the match in play without a result
+                if room.accepts_round_reports() {
+                    eprintln!("[KICK_PLAYER] ERROR: Match in room '{}' is in progress", room_id);
+                    return Err(HoverWarsError::MatchInProgress);
+                }
+                let Ok(player_chain) = chain_id.parse::<ChainId>() else {
+                    return Err(HoverWarsError::InvalidChainId(chain_id));
+                };
+                let timestamp = self.runtime.system_time().micros().to_string();
+
+                self.unsubscribe_from_chain(&room, player_chain);
+                room.kick_player(&chain_id, ban);
+                self.emit_room_event(
+                    &mut room,
+                    HoverWarsEvent::PlayerKicked {
+                        player_chain_id: chain_id.clone(),
+                        banned: ban,
+                        timestamp: timestamp.clone(),
+                    }
+                );
+
+                let message = CrossChainMessage::KickedNotification {
+                    room_id: room_id.clone(),
+                    banned: ban,
+                    timestamp,
+                };
+                self.runtime.send_message(player_chain, message);
+
+                self.save_room(&room);
+                self.publish_listing(&room);
+                eprintln!("[KICK_PLAYER] {} kicked from room '{}'{}", chain_id, room_id, if ban { " and banned" } else { "" });
+                Ok(OperationResponse::for_room(&room))
+            }
+
+            Operation::Concede { room_id, reason } => {
+                let Some(room) = self.load_room(&room_id).await else {
+                    eprintln!("[CONCEDE] ERROR: Room '{}' not found", room_id);
+                    return Err(HoverWarsError::RoomNotFound(room_id));
+                };
+                let current_chain = self.runtime.chain_id().to_string();
+
+                let Some(player) = room.player(&current_chain).cloned() else {
+                    eprintln!("[CONCEDE] ERROR: Caller is not a player in this room");
+                    return Err(HoverWarsError::NotInRoom);
+                };
+
+                if !room.accepts_round_reports() {
+                    eprintln!("[CONCEDE] ERROR: Match is not in progress");
+                    return Err(HoverWarsError::MatchNotInProgress);
+                }
+
+                let reason = reason.filter(|reason| !reason.trim().is_empty()).unwrap_or_else(|| "conceded".to_string());
+
+                if room.host_chain_id == current_chain {
+                    let room = self.concede(room, &player, reason);
+                    Ok(OperationResponse::for_room(&room))
+                } else {
+                    // The host ends the match; our copy follows through the room's stream
+                    let Ok(host_chain) = room.host_chain_id.parse::<ChainId>() else {
+                        return Err(HoverWarsError::InvalidChainId(room.host_chain_id));
+                    };
+                    self.runtime.send_message(host_chain, CrossChainMessage::ConcedeNotification { room_id, reason });
+                    Ok(OperationResponse::for_room(&room))
+                }
+            }
+
+            Operation::ClaimTimeoutWin { room_id } => {
+                let Some(room) = self.load_room(&room_id).await else {
+                    eprintln!("[TIMEOUT_CLAIM] ERROR: Room '{}' not found", room_id);
+                    return Err(HoverWarsError::RoomNotFound(room_id));
+                };
+                let current_chain = self.runtime.chain_id().to_string();
+
+                let Some(player) = room.player(&current_chain).cloned() else {
+                    eprintln!("[TIMEOUT_CLAIM] ERROR: Caller is not a player in this room");
+                    return Err(HoverWarsError::NotInRoom);
+                };
+
+                if room.host_chain_id == current_chain {
+                    let room = self.claim_timeout_win(room, &player)?;
+                    Ok(OperationResponse::for_room(&room))
+                } else {
+                    // Round reports are only known to the host, which checks the claim again
+                    if !room.accepts_round_reports() {
+                        eprintln!("[TIMEOUT_CLAIM] ERROR: Match is not in progress");
+                        return Err(HoverWarsError::MatchNotInProgress);
+                    }
+                    let now = self.runtime.system_time().micros();
+                    if !room.report_deadline_passed(now) {
+                        eprintln!("[TIMEOUT_CLAIM] ERROR: Round {} report deadline has not passed", room.current_round);
+                        return Err(HoverWarsError::DeadlineNotReached);
+                    }
+
+                    let Ok(host_chain) = room.host_chain_id.parse::<ChainId>() else {
+                        return Err(HoverWarsError::InvalidChainId(room.host_chain_id));
+                    };
+                    self.runtime.send_message(host_chain, CrossChainMessage::TimeoutClaim { room_id });
+                    Ok(OperationResponse::for_room(&room))
+                }
+            }
+
+            Operation::RequestRematch { room_id, swap_sides } => {
+                let Some(mut room) = self.load_room(&room_id).await else {
+                    eprintln!("[REQUEST_REMATCH] ERROR: Room '{}' not found", room_id);
+                    return Err(HoverWarsError::RoomNotFound(room_id));
+                };
+                let current_chain = self.runtime.chain_id().to_string();
+
+                if room.player(&current_chain).is_none() {
+                    eprintln!("[REQUEST_REMATCH] ERROR: Caller is not a player in this room");
+                    return Err(HoverWarsError::NotInRoom);
+                }
+
+                if room.match_state != MatchState::Finished {
+                    eprintln!("[REQUEST_REMATCH] ERROR: Match in room '{}' is not finished", room_id);
+                    return Err(HoverWarsError::MatchNotFinished);
+                }
+
+                if room.host_chain_id == current_chain {
+                    let room = self.request_rematch(room, &current_chain, swap_sides);
+                    Ok(OperationResponse::for_room(&room))
+                } else {
+                    // Guest records its request and proposes the rematch to the host
+                    room.request_rematch(&current_chain, swap_sides);
+                    self.save_room(&room);
+
+                    let Ok(host_chain) = room.host_chain_id.parse::<ChainId>() else {
+                        return Err(HoverWarsError::InvalidChainId(room.host_chain_id));
+                    };
+                    self.runtime.send_message(host_chain, CrossChainMessage::RematchRequest { room_id, swap_sides });
+                    Ok(OperationResponse::for_room(&room))
+                }
+            }
+
+            Operation::AcceptRematch { room_id } => {
+                let Some(mut room) = self.load_room(&room_id).await else {
+                    eprintln!("[ACCEPT_REMATCH] ERROR: Room '{}' not found", room_id);
+                    return Err(HoverWarsError::RoomNotFound(room_id));
+                };
+                let current_chain = self.runtime.chain_id().to_string();
+
+                if room.player(&current_chain).is_none() {
+                    eprintln!("[ACCEPT_REMATCH] ERROR: Caller is not a player in this room");
+                    return Err(HoverWarsError::NotInRoom);
+                }
+
+                if room.match_state != MatchState::Finished {
+                    eprintln!("[ACCEPT_REMATCH] ERROR: Match in room '{}' is not finished", room_id);
+                    return Err(HoverWarsError::MatchNotFinished);
+                }
+
+                if room.rematch.is_none() {
+                    eprintln!("[ACCEPT_REMATCH] ERROR: No rematch requested in room '{}'", room_id);
+                    return Err(HoverWarsError::NoRematchRequested);
+                }
+
+                if room.host_chain_id == current_chain {
+                    let room = self.accept_rematch(room, &current_chain);
+                    Ok(OperationResponse::for_room(&room))
+                } else {
+                    // Guest records its acceptance and tells the host
+                    room.accept_rematch(&current_chain);
+                    self.save_room(&room);
+
+                    let Ok(host_chain) = room.host_chain_id.parse::<ChainId>() else {
+                        return Err(HoverWarsError::InvalidChainId(room.host_chain_id));
+                    };
+                    self.runtime.send_message(host_chain, CrossChainMessage::RematchAccept { room_id });
+                    Ok(OperationResponse::for_room(&room))
+                }
             }
         }
     }
 }
 
-impl Contract for HoverWarsContract {
-    type Message = CrossChainMessage;
-    type InstantiationArgument = ();
-    type Parameters = ();
-    type EventValue = HoverWarsEvent;
+impl Contract for HoverWarsContract {
+    type Message = CrossChainMessage;
+    type InstantiationArgument = ();
+    type Parameters = HoverWarsParameters;
+    type EventValue = RoomEvent;
+
+    async fn load(runtime: ContractRuntime<Self>) -> Self {
+        let state = HoverWarsState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        HoverWarsContract { state, runtime }
+    }
+
+    async fn instantiate(&mut self, _argument: ()) {
+        self.state.rooms_created.set(0);
+        eprintln!("[INIT] HoverWars contract initialized on chain {:?}", self.runtime.chain_id());
+    }
+
+    async fn execute_operation(&mut self, operation: Operation) -> Self::Response {
+        let operation_kind = operation.kind();
+        let result = self.run_operation(operation).await;
+
+        // Mutations only schedule operations, so clients read their outcome back from the state
+        let sequence = *self.state.operations_executed.get();
+        self.state.operations_executed.set(sequence + 1);
+        let outcome = OperationOutcome {
+            operation: operation_kind.to_string(),
+            sequence,
+            response: result.as_ref().ok().cloned(),
+            error: result.as_ref().err().map(|error| error.to_string()),
+            timestamp: self.runtime.system_time().micros().to_string(),
+        };
+        self.state.operation_outcomes.insert(operation_kind, outcome).expect("Failed to save operation outcome");
+
+        result
+    }
+
+    async fn execute_message(&mut self, message: Self::Message) {
+        match message {
+            CrossChainMessage::JoinRequest { room_id, player_chain_id, player_name, invite_code } => {
+                if self.runtime.message_is_bouncing() == Some(true) {
+                    eprintln!("[JOIN_REQUEST] Join request for room '{}' bounced back", room_id);
+                    let reason = "host chain could not process the join request".to_string();
+                    self.update_join_attempt(&room_id, JoinStatus::Rejected, Some(reason)).await;
+                    return;
+                }
+
+                eprintln!("[JOIN_REQUEST] Received join request for room '{}' from player '{}' on chain {:?}", room_id, player_name, player_chain_id);
+
+                if !self.authenticate_origin("JoinRequest", &room_id, &player_chain_id.to_string()) {
+                    return;
+                }
+
+                let hosted_room = match self.load_room(&room_id).await {
+                    Some(room) if self.authenticate_host_chain("JoinRequest", &room) => Some(room),
+                    _ => None,
+                };
+
+                if let Some(mut room) = hosted_room {
+                    if room.player(&player_chain_id.to_string()).is_some() {
+                        eprintln!("[JOIN_REQUEST] ERROR: Player is already in the room");
+                        let message = CrossChainMessage::JoinRejected {
+                            room_id,
+                            reason: HoverWarsError::AlreadyInRoom,
+                        };
+                        self.runtime.send_message(player_chain_id, message);
+                        return;
+                    }
+
+                    if room.is_banned(&player_chain_id.to_string()) {
+                        eprintln!("[JOIN_REQUEST] ERROR: Player is banned from room '{}'", room_id);
+                        let message = CrossChainMessage::JoinRejected {
+                            room_id,
+                            reason: HoverWarsError::Banned,
+                        };
+                        self.runtime.send_message(player_chain_id, message);
+                        return;
+                    }
+
+                    if !room.access.admits(&player_chain_id.to_string(), invite_code.as_deref()) {
+                        eprintln!("[JOIN_REQUEST] ERROR: Player is not invited to private room '{}'", room_id);
+                        let message = CrossChainMessage::JoinRejected {
+                            room_id,
+                            reason: HoverWarsError::NotInvited,
+                        };
+                        self.runtime.send_message(player_chain_id, message);
+                        return;
+                    }
+
+                    // A slot freed during a match stays empty until the match is over
+                    if room.accepts_round_reports() {
+                        eprintln!("[JOIN_REQUEST] ERROR: Room '{}' has a match in progress", room_id);
+                        let message = CrossChainMessage::JoinRejected {
+                            room_id,
+                            reason: HoverWarsError::MatchInProgress,
+                        };
+                        self.runtime.send_message(player_chain_id, message);
+                        return;
+                    }
+
+                    let timestamp = self.runtime.system_time().micros().to_string();
+
+                    // Add the player to the team with an open slot
+                    let Some(new_player) = room.add_player(player_chain_id.to_string(), player_name.clone()) else {
+                        eprintln!("[JOIN_REQUEST] ERROR: Room is full");
+                        let message = CrossChainMessage::JoinRejected {
+                            room_id,
+                            reason: HoverWarsError::RoomFull,
+                        };
+                        self.runtime.send_message(player_chain_id, message);
+                        return;
+                    };
+
+                    // Subscribe to the new player's chain
+                    self.subscribe_to_player(&room, &player_chain_id.to_string());
+
+                    self.runtime.send_message(player_chain_id, CrossChainMessage::JoinAccepted { room_id: room_id.clone() });
+
+                    // Send initial state to the new player; later changes reach it through the room's stream
+                    let sync_message = CrossChainMessage::InitialStateSync {
+                        room_data: room.clone(),
+                    };
+                    self.runtime.send_message(player_chain_id, sync_message);
+
+                    // Emit player joined event
+                    let team = new_player.team;
+                    self.emit_room_event(
+                        &mut room,
+                        HoverWarsEvent::PlayerJoined {
+                            player: new_player,
+                            timestamp: timestamp.clone(),
+                        }
+                    );
+                    eprintln!("[JOIN_REQUEST] Player '{}' joined room '{}' on {:?} Team", player_name, room_id, team);
+
+                    // Ask everyone to ready up once both rosters are full
+                    if room.is_full() {
+                        self.begin_ready_check(&mut room);
+                        eprintln!("[JOIN_REQUEST] Rosters full in room '{}'. Ready check started", room_id);
+                    }
+
+                    // Keep the directory listing current
+                    self.publish_listing(&room);
+
+                    self.save_room(&room);
+                } else {
+                    eprintln!("[JOIN_REQUEST] ERROR: Room '{}' not found", room_id);
+                    let message = CrossChainMessage::JoinRejected {
+                        room_id: room_id.clone(),
+                        reason: HoverWarsError::RoomNotFound(room_id),
+                    };
+                    self.runtime.send_message(player_chain_id, message);
+                }
+            }
+
+            CrossChainMessage::JoinAccepted { room_id } => {
+                eprintln!("[JOIN_ACCEPTED] Join request for room '{}' accepted", room_id);
+
+                let Some(attempt) = self.state.join_attempts.get(&room_id).await.expect("Failed to load join attempt") else {
+                    eprintln!("[JOIN_ACCEPTED] ERROR: No join request pending for room '{}'", room_id);
+                    return;
+                };
+                if !self.authenticate_origin("JoinAccepted", &room_id, &attempt.host_chain_id) {
+                    return;
+                }
+
+                self.update_join_attempt(&room_id, JoinStatus::Accepted, None).await;
+            }
+
+            CrossChainMessage::JoinRejected { room_id, reason } => {
+                eprintln!("[JOIN_REJECTED] Join request for room '{}' rejected: {}", room_id, reason);
+
+                let Some(attempt) = self.state.join_attempts.get(&room_id).await.expect("Failed to load join attempt") else {
+                    eprintln!("[JOIN_REJECTED] ERROR: No join request pending for room '{}'", room_id);
+                    return;
+                };
+                if !self.authenticate_origin("JoinRejected", &room_id, &attempt.host_chain_id) {
+                    return;
+                }
+
+                self.update_join_attempt(&room_id, JoinStatus::Rejected, Some(reason.to_string())).await;
+            }
+
+            CrossChainMessage::InitialStateSync { room_data } => {
+                eprintln!("[INITIAL_STATE_SYNC] Received state of room '{}' at version {} from host", room_data.room_id, room_data.room_version);
+
+                // The snapshot names its own host, so the expected sender comes from local state:
+                // the host of the local copy, or the host a join request was sent to
+                let local_room = self.load_room(&room_data.room_id).await;
+                let expected_host = match &local_room {
+                    Some(local_room) if local_room.host_chain_id == self.runtime.chain_id().to_string() => {
+                        let origin = self.runtime.message_origin_chain_id().map(|chain| chain.to_string());
+                        self.log_rejected_message("InitialStateSync", &room_data.room_id, origin, "no snapshot of a room hosted here");
+                        return;
+                    }
+                    Some(local_room) => local_room.host_chain_id.clone(),
+                    None => match self.state.join_attempts.get(&room_data.room_id).await.expect("Failed to load join attempt") {
+                        Some(attempt) => attempt.host_chain_id,
+                        None => {
+                            let origin = self.runtime.message_origin_chain_id().map(|chain| chain.to_string());
+                            self.log_rejected_message("InitialStateSync", &room_data.room_id, origin, "host of a room joined from this chain");
+                            return;
+                        }
+                    },
+                };
+                if !self.authenticate_origin("InitialStateSync", &room_data.room_id, &expected_host) {
+                    return;
+                }
+                if room_data.host_chain_id != expected_host {
+                    let origin = self.runtime.message_origin_chain_id().map(|chain| chain.to_string());
+                    let expected = format!("snapshot naming host {}", expected_host);
+                    self.log_rejected_message("InitialStateSync", &room_data.room_id, origin, &expected);
+                    return;
+                }
+
+                if let Some(local_room) = &local_room {
+                    // Events may have moved this copy past the snapshot already
+                    if local_room.room_version > room_data.room_version {
+                        eprintln!("[INITIAL_STATE_SYNC] Ignoring stale state (local version {})", local_room.room_version);
+                        return;
+                    }
+                    // The snapshot may cover a MatchEnded event this chain never processed
+                    if local_room.match_state != MatchState::Finished && room_data.match_state == MatchState::Finished {
+                        let timestamp = self.runtime.system_time().micros().to_string();
+                        self.record_match_end(&room_data, timestamp);
+                    }
+                }
+                self.state.pending_state_syncs.remove(&room_data.room_id).expect("Failed to remove pending state sync");
+
+                let host_chain_id = room_data.host_chain_id.clone();
+                let already_subscribed = self.state.subscribed_to_host.get(&room_data.room_id)
+                    .await
+                    .expect("Failed to load subscription")
+                    .is_some_and(|h| h == host_chain_id);
+
+                if !already_subscribed {
+                    if let Ok(host_chain) = host_chain_id.parse() {
+                        let app_id = self.runtime.application_id().forget_abi();
+                        let stream = StreamName::from(room_data.stream_name());
+                        self.runtime.subscribe_to_events(host_chain, app_id, stream);
 
-    async fn load(runtime: ContractRuntime<Self>) -> Self {
-        let state = HoverWarsState::load(runtime.root_view_storage_context())
-            .await
-            .expect("Failed to load state");
-        HoverWarsContract { state, runtime }
-    }
+                        self.state.subscribed_to_host
+                            .insert(&room_data.room_id, host_chain_id)
+                            .expect("Failed to save subscription");
+                        eprintln!("[INITIAL_STATE_SYNC] Subscribed to host game_events stream");
+                    }
+                }
 
-    async fn instantiate(&mut self, _argument: ()) {
-        self.state.room.set(None);
-        self.state.subscribed_to_host.set(None);
-        eprintln!("[INIT] HoverWars contract initialized on chain {:?}", self.runtime.chain_id());
-    }
+                self.save_room(&room_data);
+                eprintln!("[INITIAL_STATE_SYNC] Player now has complete room state");
+            }
 
-    async fn execute_operation(&mut self, operation: Operation) -> () {
-        match operation {
-            Operation::CreateLobby { host_name } => {
-                let host_chain_id = self.runtime.chain_id().to_string();
-                let timestamp = self.runtime.system_time().micros().to_string();
-                
-                let room = hoverwars::GameRoom::new(host_chain_id.clone(), host_name.clone(), timestamp);
-                self.state.room.set(Some(room.clone()));
-                
-                // Host subscribes to self (will subscribe to opponent when they join)
-                self.subscribe_to_player(&host_chain_id);
-                
-                eprintln!("[CREATE_LOBBY] Lobby created by host '{}' (Blue Team)", host_name);
-            }
-
-            Operation::JoinLobby { host_chain_id, player_name } => {
-                eprintln!("[JOIN_LOBBY] Sending join request to host chain '{}' from player '{}'", host_chain_id, player_name);
-                
-                if let Ok(target_chain) = host_chain_id.parse() {
-                    let message = CrossChainMessage::JoinRequest {
-                        player_chain_id: self.runtime.chain_id(),
-                        player_name,
+            CrossChainMessage::RequestStateSync { room_id, known_version } => {
+                eprintln!("[REQUEST_STATE_SYNC] Room '{}' state requested from version {}", room_id, known_version);
+
+                if let Some(room) = self.load_room(&room_id).await {
+                    let Some(player) = self.authenticate_member("RequestStateSync", &room) else {
+                        return;
                     };
-                    
-                    self.runtime.send_message(target_chain, message);
-                    eprintln!("[JOIN_LOBBY] Join request sent to chain {}", host_chain_id);
-                } else {
-                    eprintln!("[JOIN_LOBBY] Invalid host_chain_id format: {}", host_chain_id);
+                    if !self.authenticate_host_chain("RequestStateSync", &room) {
+                        return;
+                    }
+
+                    if let Ok(player_chain) = player.chain_id.parse::<ChainId>() {
+                        self.runtime.send_message(player_chain, CrossChainMessage::InitialStateSync { room_data: room });
+                    }
                 }
             }
 
-            Operation::ReportRoundResult { result } => {
-                if let Some(mut room) = self.state.room.get().clone() {
-                    let current_chain = self.runtime.chain_id();
-                    
-                    // Allow ANY player in the room to report
-                    let is_host = room.host_chain_id == current_chain.to_string();
-                    let is_guest = room.red_player.as_ref().map_or(false, |p| p.chain_id == current_chain.to_string());
-                    
-                    if !is_host && !is_guest {
-                         eprintln!("[REPORT_ROUND] ERROR: Caller is not a player in this room");
-                         return;
+            CrossChainMessage::ReadyUpdate { room_id, ready } => {
+                eprintln!("[READY_UPDATE] Received room '{}' readiness: {}", room_id, ready);
+
+                if let Some(room) = self.load_room(&room_id).await {
+                    let Some(player) = self.authenticate_member("ReadyUpdate", &room) else {
+                        return;
+                    };
+                    if !self.authenticate_host_chain("ReadyUpdate", &room) {
+                        return;
                     }
 
-                    if room.match_state != MatchState::InProgress {
-                        eprintln!("[REPORT_ROUND] ERROR: Match is not in progress");
+                    if room.match_state != MatchState::ReadyCheck {
+                        eprintln!("[READY_UPDATE] ERROR: Room '{}' is not in a ready check", room_id);
                         return;
                     }
-                    
-                    let timestamp = self.runtime.system_time().micros().to_string();
-                    let round_number = room.current_round;
-                    let match_ended = room.apply_round_result(result, timestamp.clone());
-                    
-                    let blue_score = room.get_blue_score();
-                    let red_score = room.get_red_score();
-                    
-                    self.state.room.set(Some(room.clone()));
-                    
-                    // Emit round completed event
-                    self.runtime.emit(
-                        format!("game_events_{}", room.room_id).into(),
-                        &HoverWarsEvent::RoundCompleted {
-                            round_number,
-                            result,
-                            blue_score,
-                            red_score,
-                            timestamp: timestamp.clone(),
-                        }
-                    );
-                    
-                    eprintln!("[REPORT_ROUND] Round {} completed: {:?}. Blue: {}, Red: {}", 
-                             round_number, result, blue_score, red_score);
-                    
-                    // Send notification to OPPONENT
-                    // If Host reported -> send to Guest (Red)
-                    // If Guest reported -> send to Host (Blue)
-                    let target_chain_str = if is_host {
-                         room.red_player.as_ref().map(|p| p.chain_id.clone())
-                    } else {
-                         Some(room.host_chain_id.clone())
+
+                    self.update_readiness(room, &player.chain_id, ready);
+                }
+            }
+
+            CrossChainMessage::RoundReport { room_id, round_number, result, stats } => {
+                eprintln!("[ROUND_REPORT] Received room '{}' round {} report: {:?}", room_id, round_number, result);
+
+                if let Some(mut room) = self.load_room(&room_id).await {
+                    let Some(reporter) = self.authenticate_member("RoundReport", &room) else {
+                        return;
                     };
+                    if !self.authenticate_host_chain("RoundReport", &room) {
+                        return;
+                    }
 
-                    if let Some(target_chain_id) = target_chain_str {
-                        if let Ok(target_chain) = target_chain_id.parse::<ChainId>() {
-                             if match_ended {
-                                 let message = CrossChainMessage::MatchEndNotification {
-                                     winner: room.winner,
-                                     blue_score,
-                                     red_score,
-                                     timestamp: timestamp.clone(),
-                                 };
-                                 self.runtime.send_message(target_chain, message);
-                                 
-                                 // Emit match ended event
-                                 self.runtime.emit(
-                                     format!("game_events_{}", room.room_id).into(),
-                                     &HoverWarsEvent::MatchEnded {
-                                         winner: room.winner,
-                                         blue_score,
-                                         red_score,
-                                         timestamp,
-                                     }
-                                 );
-                                 eprintln!("[REPORT_ROUND] Match ended! Winner: {:?}", room.winner);
-                             } else {
-                                 let message = CrossChainMessage::RoundResultNotification {
-                                     round_number,
-                                     result,
-                                     blue_score,
-                                     red_score,
-                                     timestamp,
-                                 };
-                                 self.runtime.send_message(target_chain, message);
-                             }
-                        }
+                    if !room.accepts_round_reports() {
+                        eprintln!("[ROUND_REPORT] ERROR: Match is not in progress");
+                        return;
+                    }
+
+                    if room.has_round(round_number) {
+                        eprintln!("[ROUND_REPORT] Round {} is already recorded, ignoring", round_number);
+                        return;
+                    }
+
+                    if round_number != room.current_round {
+                        eprintln!("[ROUND_REPORT] ERROR: Report is for round {}, current round is {}", round_number, room.current_round);
+                        return;
+                    }
+
+                    if room.has_reported(reporter.team) {
+                        eprintln!("[ROUND_REPORT] ERROR: {:?} already reported round {}", reporter.team, round_number);
+                        return;
                     }
+
+                    if let Err(error) = stats.validate(&room, reporter.team, result) {
+                        eprintln!("[ROUND_REPORT] ERROR: {}", error);
+                        return;
+                    }
+
+                    let confirmation = room.submit_round_report(reporter.team, result, stats);
+                    self.resolve_round_reports(room, confirmation);
                 }
             }
 
-            Operation::LeaveLobby => {
-                if let Some(room) = self.state.room.get().clone() {
-                    let current_chain = self.runtime.chain_id();
-                    let timestamp = self.runtime.system_time().micros().to_string();
-                    
-                    if room.host_chain_id == current_chain.to_string() {
-                        // Host leaving - delete room and notify opponent
-                        eprintln!("[LEAVE_LOBBY] Host leaving, deleting room");
-                        
-                        if let Some(ref red_player) = room.red_player {
-                            if let Ok(red_chain) = red_player.chain_id.parse::<ChainId>() {
-                                let message = CrossChainMessage::RoomDeleted {
-                                    timestamp: timestamp.clone(),
-                                };
-                                self.runtime.send_message(red_chain, message);
-                                
-                                // Unsubscribe from opponent
-                                let app_id = self.runtime.application_id().forget_abi();
-                                let stream = StreamName::from(format!("game_events_{}", room.room_id));
-                                self.runtime.unsubscribe_from_events(red_chain, app_id, stream);
-                            }
-                        }
-                        
-                        // Clear room
-                        self.state.room.set(None);
-                        
-                    } else {
-                        // Guest leaving - notify host
-                        eprintln!("[LEAVE_LOBBY] Guest leaving");
-                        
-                        if let Ok(host_chain) = room.host_chain_id.parse::<ChainId>() {
-                            let message = CrossChainMessage::PlayerLeftNotification {
-                                player_chain_id: current_chain.to_string(),
-                                timestamp: timestamp.clone(),
-                            };
-                            self.runtime.send_message(host_chain, message);
-                            
-                            // Unsubscribe from host
-                            let app_id = self.runtime.application_id().forget_abi();
-                            let stream = StreamName::from(format!("game_events_{}", room.room_id));
-                            self.runtime.unsubscribe_from_events(host_chain, app_id, stream);
-                        }
-                        
-                        // Clear local state
-                        self.state.room.set(None);
-                        self.state.subscribed_to_host.set(None);
+            CrossChainMessage::RoomDeleted { room_id, timestamp } => {
+                eprintln!("[ROOM_DELETED] Received deletion of room '{}' at {}", room_id, timestamp);
+
+                if let Some(room) = self.load_room(&room_id).await {
+                    if !self.authenticate_origin("RoomDeleted", &room_id, &room.host_chain_id) {
+                        return;
+                    }
+
+                    if let Ok(host_chain) = room.host_chain_id.parse() {
+                        self.unsubscribe_from_chain(&room, host_chain);
                     }
+
+                    self.remove_room(&room_id);
+                    eprintln!("[ROOM_DELETED] Local state cleared");
                 }
             }
-        }
-    }
 
-    async fn execute_message(&mut self, message: Self::Message) {
-        match message {
-            CrossChainMessage::JoinRequest { player_chain_id, player_name } => {
-                eprintln!("[JOIN_REQUEST] Received join request from player '{}' on chain {:?}", player_name, player_chain_id);
-                
-                if let Some(mut room) = self.state.room.get().clone() {
-                    if room.red_player.is_some() {
-                        eprintln!("[JOIN_REQUEST] ERROR: Room already has opponent");
+            CrossChainMessage::PlayerLeftNotification { room_id, player_chain_id, timestamp } => {
+                eprintln!("[PLAYER_LEFT] Player {:?} left room '{}' at {}", player_chain_id, room_id, timestamp);
+
+                if let Some(mut room) = self.load_room(&room_id).await {
+                    // Only a guest itself can announce that it left
+                    let Some(leaving_player) = self.authenticate_member("PlayerLeftNotification", &room) else {
                         return;
-                    }
-                    
-                    let timestamp = self.runtime.system_time().micros().to_string();
-                    
-                    // Add red player
-                    room.add_red_player(player_chain_id.to_string(), player_name.clone());
-                    self.state.room.set(Some(room.clone()));
-                    
-                    // Subscribe to opponent's chain
-                    self.subscribe_to_player(&player_chain_id.to_string());
-                    
-                    // Send initial state to the new player
-                    let sync_message = CrossChainMessage::InitialStateSync {
-                        room_data: room.clone(),
                     };
-                    self.runtime.send_message(player_chain_id, sync_message);
-                    
-                    // Emit player joined event
-                    let new_player = room.red_player.clone().unwrap();
-                    self.runtime.emit(
-                        format!("game_events_{}", room.room_id).into(),
-                        &HoverWarsEvent::PlayerJoined {
-                            player: new_player,
-                            timestamp: timestamp.clone(),
+                    if !self.authenticate_host_chain("PlayerLeftNotification", &room) {
+                        return;
+                    }
+                    if leaving_player.chain_id != player_chain_id || leaving_player.chain_id == room.host_chain_id {
+                        eprintln!("[PLAYER_LEFT] ERROR: Sender cannot leave on behalf of {}", player_chain_id);
+                        return;
+                    }
+
+                    // Unsubscribe from leaving player
+                    if let Ok(player_chain) = player_chain_id.parse::<ChainId>() {
+                        self.unsubscribe_from_chain(&room, player_chain);
+                    }
+
+                    // A match in play only goes on if the player's team keeps someone
+                    if room.accepts_round_reports() && !room.can_continue_without(&player_chain_id) {
+                        let forfeit = ForfeitRecord {
+                            team: leaving_player.team,
+                            player_chain_id: player_chain_id.clone(),
+                            reason: "player left the room".to_string(),
+                            round_number: room.current_round,
+                        };
+                        if room.forfeit_match(forfeit) {
+                            self.end_match(&mut room, timestamp.clone());
+                            eprintln!("[PLAYER_LEFT] Match forfeited to {:?}", room.winner);
                         }
-                    );
-                    
-                    // Emit match started event
-                    self.runtime.emit(
-                        format!("game_events_{}", room.room_id).into(),
-                        &HoverWarsEvent::MatchStarted {
-                            blue_player_name: room.blue_player.name.clone(),
-                            red_player_name: player_name.clone(),
+                    }
+
+                    // Remove the player (the room waits for the rosters to fill again unless a match goes on)
+                    room.remove_player(&player_chain_id);
+
+                    // Emit player left event
+                    self.emit_room_event(
+                        &mut room,
+                        HoverWarsEvent::PlayerLeft {
+                            player_chain_id: player_chain_id.clone(),
                             timestamp,
                         }
                     );
-                    
-                    eprintln!("[JOIN_REQUEST] Player '{}' joined as Red Team. Match started!", player_name);
+
+                    self.save_room(&room);
+                    self.publish_listing(&room);
+                    eprintln!("[PLAYER_LEFT] Room '{}' is now {:?}", room_id, room.match_state);
                 }
             }
 
-            CrossChainMessage::InitialStateSync { room_data } => {
-                eprintln!("[INITIAL_STATE_SYNC] Received initial room state from host");
-                
-                let host_chain_id = room_data.host_chain_id.clone();
-                let already_subscribed = self.state.subscribed_to_host.get()
-                    .as_ref()
-                    .map(|h| h == &host_chain_id)
-                    .unwrap_or(false);
-                
-                if !already_subscribed {
-                    if let Ok(host_chain) = host_chain_id.parse() {
-                        let app_id = self.runtime.application_id().forget_abi();
-                        let stream = StreamName::from(format!("game_events_{}", room_data.room_id));
-                        self.runtime.subscribe_to_events(host_chain, app_id, stream);
-                        
-                        self.state.subscribed_to_host.set(Some(host_chain_id));
-                        eprintln!("[INITIAL_STATE_SYNC] Subscribed to host game_events stream");
+            CrossChainMessage::HostTransfer { room_data, new_host_chain_id } => {
+                let room_id = room_data.room_id.clone();
+                eprintln!("[HOST_TRANSFER] Room '{}' handed over to {}", room_id, new_host_chain_id);
+
+                let Some(local_room) = self.load_room(&room_id).await else {
+                    eprintln!("[HOST_TRANSFER] ERROR: Room '{}' not found", room_id);
+                    return;
+                };
+                if !self.authenticate_origin("HostTransfer", &room_id, &local_room.host_chain_id)
+                    || room_data.host_chain_id != local_room.host_chain_id
+                {
+                    return;
+                }
+
+                // A forfeit may reach us before the old host's MatchEnded event does
+                if local_room.match_state != MatchState::Finished && room_data.match_state == MatchState::Finished {
+                    let timestamp = self.runtime.system_time().micros().to_string();
+                    self.record_match_end(&room_data, timestamp);
+                }
+
+                // Stop following the old host's stream
+                if let Ok(old_host_chain) = room_data.host_chain_id.parse() {
+                    self.unsubscribe_from_chain(&room_data, old_host_chain);
+                }
+                self.state.subscribed_to_host.remove(&room_id).expect("Failed to remove subscription");
+
+                let mut room = room_data;
+                room.migrate_host(&new_host_chain_id);
+                let current_chain = self.runtime.chain_id().to_string();
+
+                if new_host_chain_id == current_chain {
+                    // New host follows itself and every remaining guest
+                    self.subscribe_to_player(&room, &current_chain);
+                    let guest_chains: Vec<String> = room.guests().map(|p| p.chain_id.clone()).collect();
+                    for guest_chain in guest_chains {
+                        self.subscribe_to_player(&room, &guest_chain);
                     }
+                    self.save_room(&room);
+                    self.publish_listing(&room);
+                    eprintln!("[HOST_TRANSFER] This chain now hosts room '{}'", room_id);
+                } else if let Ok(new_host_chain) = new_host_chain_id.parse() {
+                    let app_id = self.runtime.application_id().forget_abi();
+                    self.runtime.subscribe_to_events(new_host_chain, app_id, StreamName::from(room.stream_name()));
+                    self.state.subscribed_to_host
+                        .insert(&room_id, new_host_chain_id)
+                        .expect("Failed to save subscription");
+                    self.save_room(&room);
                 }
-                
-                self.state.room.set(Some(room_data));
-                eprintln!("[INITIAL_STATE_SYNC] Player now has complete room state");
             }
 
-            CrossChainMessage::RoundResultNotification { round_number, result, blue_score, red_score, timestamp } => {
-                eprintln!("[ROUND_RESULT] Received round {} result: {:?}", round_number, result);
-                
-                if let Some(mut room) = self.state.room.get().clone() {
-                    // Update local scores
-                    room.blue_player.score = blue_score;
-                    if let Some(ref mut red) = room.red_player {
-                        red.score = red_score;
+            CrossChainMessage::ConcedeNotification { room_id, reason } => {
+                eprintln!("[CONCEDE] Received concession of room '{}': {}", room_id, reason);
+
+                if let Some(room) = self.load_room(&room_id).await {
+                    let Some(player) = self.authenticate_member("ConcedeNotification", &room) else {
+                        return;
+                    };
+                    if !self.authenticate_host_chain("ConcedeNotification", &room) {
+                        return;
                     }
-                    room.current_round = round_number + 1;
-                    
-                    room.round_history.push(hoverwars::RoundHistoryEntry {
-                        round_number,
-                        result,
-                        blue_score_after: blue_score,
-                        red_score_after: red_score,
-                        timestamp,
-                    });
-                    
-                    self.state.room.set(Some(room));
-                    eprintln!("[ROUND_RESULT] Local state updated. Blue: {}, Red: {}", blue_score, red_score);
+
+                    if !room.accepts_round_reports() {
+                        eprintln!("[CONCEDE] ERROR: Match is not in progress");
+                        return;
+                    }
+
+                    self.concede(room, &player, reason);
                 }
             }
 
-            CrossChainMessage::MatchEndNotification { winner, blue_score, red_score, timestamp } => {
-                eprintln!("[MATCH_END] Received match end notification. Winner: {:?}", winner);
-                
-                if let Some(mut room) = self.state.room.get().clone() {
-                    room.blue_player.score = blue_score;
-                    if let Some(ref mut red) = room.red_player {
-                        red.score = red_score;
+            CrossChainMessage::TimeoutClaim { room_id } => {
+                eprintln!("[TIMEOUT_CLAIM] Received timeout claim for room '{}'", room_id);
+
+                if let Some(room) = self.load_room(&room_id).await {
+                    let Some(player) = self.authenticate_member("TimeoutClaim", &room) else {
+                        return;
+                    };
+                    if !self.authenticate_host_chain("TimeoutClaim", &room) {
+                        return;
+                    }
+
+                    if let Err(error) = self.claim_timeout_win(room, &player) {
+                        eprintln!("[TIMEOUT_CLAIM] ERROR: Claim by {} rejected: {}", player.chain_id, error);
                     }
-                    room.winner = winner;
-                    room.match_state = MatchState::Finished;
-                    
-                    self.state.room.set(Some(room));
-                    eprintln!("[MATCH_END] Match finished. Winner: {:?}", winner);
                 }
             }
 
-            CrossChainMessage::RoomDeleted { timestamp } => {
-                eprintln!("[ROOM_DELETED] Received room deletion message at {}", timestamp);
-                
-                if let Some(room) = self.state.room.get().clone() {
+            CrossChainMessage::KickedNotification { room_id, banned, timestamp } => {
+                eprintln!("[KICKED] Kicked from room '{}' at {} (banned: {})", room_id, timestamp, banned);
+
+                if let Some(room) = self.load_room(&room_id).await {
+                    if !self.authenticate_origin("KickedNotification", &room_id, &room.host_chain_id) {
+                        return;
+                    }
+
                     if let Ok(host_chain) = room.host_chain_id.parse() {
-                        let app_id = self.runtime.application_id().forget_abi();
-                        let stream = StreamName::from(format!("game_events_{}", room.room_id));
-                        self.runtime.unsubscribe_from_events(host_chain, app_id, stream);
+                        self.unsubscribe_from_chain(&room, host_chain);
                     }
+
+                    self.remove_room(&room_id);
+                    eprintln!("[KICKED] Local state cleared");
                 }
-                
-                self.state.room.set(None);
-                self.state.subscribed_to_host.set(None);
-                eprintln!("[ROOM_DELETED] Local state cleared");
             }
 
-            CrossChainMessage::PlayerLeftNotification { player_chain_id, timestamp } => {
-                eprintln!("[PLAYER_LEFT] Player {:?} left at {}", player_chain_id, timestamp);
-                
-                if let Some(mut room) = self.state.room.get().clone() {
-                    // Unsubscribe from leaving player
-                    if let Ok(player_chain) = player_chain_id.parse::<ChainId>() {
-                        let app_id = self.runtime.application_id().forget_abi();
-                        let stream = StreamName::from(format!("game_events_{}", room.room_id));
-                        self.runtime.unsubscribe_from_events(player_chain, app_id, stream);
-                    }
-                    
-                    // Remove red player and reset to waiting
-                    room.red_player = None;
-                    room.match_state = MatchState::WaitingForOpponent;
-                    room.current_round = 0;
-                    
-                    // Emit player left event
-                    self.runtime.emit(
-                        format!("game_events_{}", room.room_id).into(),
-                        &HoverWarsEvent::PlayerLeft {
-                            player_chain_id: player_chain_id.clone(),
-                            timestamp,
-                        }
-                    );
-                    
-                    self.state.room.set(Some(room));
-                    eprintln!("[PLAYER_LEFT] Room reset to waiting for opponent");
+            CrossChainMessage::RegisterLobby { listing } => {
+                if !self.is_directory_chain() {
+                    eprintln!("[REGISTER_LOBBY] ERROR: This chain is not the lobby directory");
+                    return;
+                }
+
+                if !self.authenticate_origin("RegisterLobby", &listing.room_id, &listing.host_chain_id) {
+                    return;
+                }
+
+                eprintln!("[REGISTER_LOBBY] Room '{}' by '{}' is now {:?}", listing.room_id, listing.host_name, listing.match_state);
+                self.state.lobby_directory
+                    .insert(&listing.room_id.clone(), listing)
+                    .expect("Failed to save lobby listing");
+            }
+
+            CrossChainMessage::EnterQueue { mode, rating, player_name } => {
+                if !self.is_matchmaking_chain() {
+                    eprintln!("[ENTER_QUEUE] ERROR: This chain is not the matchmaking chain");
+                    return;
+                }
+                let Some(player_chain) = self.runtime.message_origin_chain_id() else {
+                    return;
+                };
+
+                let entry = QueueEntry {
+                    chain_id: player_chain.to_string(),
+                    player_name,
+                    mode,
+                    rating,
+                    entered_at_micros: self.runtime.system_time().micros(),
+                };
+                // Entering again replaces the previous entry
+                self.state.matchmaking_queue.remove(&entry.chain_id).expect("Failed to remove queue entry");
+                self.enqueue(entry).await;
+            }
+
+            CrossChainMessage::LeaveQueue => {
+                if !self.is_matchmaking_chain() {
+                    eprintln!("[LEAVE_QUEUE] ERROR: This chain is not the matchmaking chain");
+                    return;
+                }
+                if let Some(player_chain) = self.runtime.message_origin_chain_id() {
+                    eprintln!("[LEAVE_QUEUE] {} left the queue", player_chain);
+                    self.state.matchmaking_queue
+                        .remove(&player_chain.to_string())
+                        .expect("Failed to remove queue entry");
+                }
+            }
+
+            CrossChainMessage::CreateMatchLobby { opponent_chain_id } => {
+                if !self.authenticate_matchmaker("CreateMatchLobby") {
+                    return;
+                }
+                let Some(matchmaking_chain) = self.runtime.message_origin_chain_id() else {
+                    return;
+                };
+
+                let ticket = self.state.queue_ticket.get().clone();
+                let Some(mut ticket) = ticket.filter(|ticket| ticket.status == QueueStatus::Queued) else {
+                    eprintln!("[CREATE_MATCH_LOBBY] No longer queued, declining match with {}", opponent_chain_id);
+                    self.runtime.send_message(matchmaking_chain, CrossChainMessage::MatchDeclined);
+                    return;
+                };
+
+                // Matchmaking pairs two players, so the lobby is one against one and only the opponent may join
+                let rules = MatchRules {
+                    team_size: 1,
+                    ..self.runtime.application_parameters().default_rules
+                };
+                let access = LobbyAccess {
+                    visibility: LobbyVisibility::Private,
+                    invite_code_hash: None,
+                    allowed_chain_ids: vec![opponent_chain_id.clone()],
+                };
+                let room = self.create_lobby(ticket.player_name.clone(), rules, access);
+
+                ticket.status = QueueStatus::Matched;
+                ticket.room_id = Some(room.room_id.clone());
+                self.state.queue_ticket.set(Some(ticket));
+
+                self.runtime.send_message(matchmaking_chain, CrossChainMessage::MatchLobbyCreated {
+                    room_id: room.room_id.clone(),
+                });
+                eprintln!("[CREATE_MATCH_LOBBY] Hosting matchmade room '{}' for {}", room.room_id, opponent_chain_id);
+            }
+
+            CrossChainMessage::MatchLobbyCreated { room_id } => {
+                if !self.is_matchmaking_chain() {
+                    eprintln!("[MATCH_LOBBY_CREATED] ERROR: This chain is not the matchmaking chain");
+                    return;
+                }
+                let Some(host_chain) = self.runtime.message_origin_chain_id() else {
+                    return;
+                };
+
+                let host_chain_id = host_chain.to_string();
+                let Some(opponent) = self.state.pending_matches.get(&host_chain_id).await.expect("Failed to load pending match") else {
+                    self.log_rejected_message("MatchLobbyCreated", &room_id, Some(host_chain_id), "paired host");
+                    return;
+                };
+                self.state.pending_matches.remove(&host_chain_id).expect("Failed to remove pending match");
+
+                if let Ok(opponent_chain) = opponent.chain_id.parse::<ChainId>() {
+                    eprintln!("[MATCH_LOBBY_CREATED] Sending {} to room '{}'", opponent.chain_id, room_id);
+                    self.runtime.send_message(opponent_chain, CrossChainMessage::JoinMatchLobby {
+                        host_chain_id,
+                        room_id,
+                    });
+                }
+            }
+
+            CrossChainMessage::MatchDeclined => {
+                if !self.is_matchmaking_chain() {
+                    eprintln!("[MATCH_DECLINED] ERROR: This chain is not the matchmaking chain");
+                    return;
+                }
+                let Some(host_chain) = self.runtime.message_origin_chain_id() else {
+                    return;
+                };
+
+                let host_chain_id = host_chain.to_string();
+                if let Some(opponent) = self.state.pending_matches.get(&host_chain_id).await.expect("Failed to load pending match") {
+                    self.state.pending_matches.remove(&host_chain_id).expect("Failed to remove pending match");
+                    // The opponent keeps its place (and widened rating window) in the queue
+                    eprintln!("[MATCH_DECLINED] {} declined, {} goes back to the queue", host_chain_id, opponent.chain_id);
+                    self.enqueue(opponent).await;
+                }
+            }
+
+            CrossChainMessage::JoinMatchLobby { host_chain_id, room_id } => {
+                if !self.authenticate_matchmaker("JoinMatchLobby") {
+                    return;
+                }
+
+                let Ok(host_chain) = host_chain_id.parse::<ChainId>() else {
+                    return;
+                };
+                let ticket = self.state.queue_ticket.get().clone();
+                let Some(mut ticket) = ticket.filter(|ticket| ticket.status == QueueStatus::Queued) else {
+                    // The host is waiting for this chain alone, so it has to hear that nobody is coming
+                    eprintln!("[JOIN_MATCH_LOBBY] No longer queued, declining room '{}'", room_id);
+                    self.runtime.send_message(host_chain, CrossChainMessage::MatchLobbyDeclined { room_id });
+                    return;
+                };
+
+                ticket.status = QueueStatus::Matched;
+                ticket.room_id = Some(room_id.clone());
+                let player_name = ticket.player_name.clone();
+                self.state.queue_ticket.set(Some(ticket));
+
+                eprintln!("[JOIN_MATCH_LOBBY] Joining matchmade room '{}' on {}", room_id, host_chain_id);
+                self.request_join(host_chain, room_id, player_name, None);
+            }
+
+            CrossChainMessage::MatchLobbyDeclined { room_id } => {
+                let current_chain = self.runtime.chain_id().to_string();
+                let Some(room) = self.load_room(&room_id).await.filter(|room| room.host_chain_id == current_chain) else {
+                    eprintln!("[MATCH_LOBBY_DECLINED] Room '{}' is not hosted here", room_id);
+                    return;
+                };
+                let Some(opponent_chain_id) = room.access.allowed_chain_ids.first().cloned() else {
+                    return;
+                };
+                if !self.authenticate_origin("MatchLobbyDeclined", &room_id, &opponent_chain_id) {
+                    return;
+                }
+
+                let ticket = self.state.queue_ticket.get().clone();
+                let Some(mut ticket) = ticket.filter(|ticket| ticket.status == QueueStatus::Matched && ticket.room_id.as_deref() == Some(room_id.as_str())) else {
+                    eprintln!("[MATCH_LOBBY_DECLINED] Room '{}' is not waiting for a matchmade opponent", room_id);
+                    return;
+                };
+                let Some(matchmaking_chain) = self.runtime.application_parameters().matchmaking_chain_id else {
+                    return;
+                };
+
+                // Nobody else may join the private lobby, so close it and look for another opponent
+                eprintln!("[MATCH_LOBBY_DECLINED] {} declined, closing room '{}' and queueing again", opponent_chain_id, room_id);
+                self.unpublish_listing(&room);
+                self.remove_room(&room_id);
+
+                self.runtime.send_message(matchmaking_chain, CrossChainMessage::EnterQueue {
+                    mode: ticket.mode.clone(),
+                    rating: ticket.rating,
+                    player_name: ticket.player_name.clone(),
+                });
+                ticket.status = QueueStatus::Queued;
+                ticket.room_id = None;
+                ticket.entered_at = self.runtime.system_time().micros().to_string();
+                self.state.queue_ticket.set(Some(ticket));
+            }
+
+            CrossChainMessage::MatchResult { archived_match } => {
+                if !self.is_leaderboard_chain() {
+                    eprintln!("[MATCH_RESULT] ERROR: This chain is not the leaderboard");
+                    return;
+                }
+
+                // Guests follow the host's stream, so the host's report is authoritative and
+                // a participant that stops producing blocks cannot hold the result back
+                let origin = self.runtime.message_origin_chain_id().map(|chain| chain.to_string());
+                if origin.as_deref() != Some(archived_match.host_chain_id.as_str()) || !archived_match.has_player(&archived_match.host_chain_id) {
+                    self.log_rejected_message("MatchResult", &archived_match.room_id, origin, "host that played in the match");
+                    return;
+                }
+
+                let result_key = archived_match.result_key();
+                if self.state.counted_matches.contains(&result_key).await.expect("Failed to load counted matches") {
+                    eprintln!("[MATCH_RESULT] Match '{}' is already counted", result_key);
+                    return;
+                }
+                self.state.counted_matches
+                    .insert(&result_key)
+                    .expect("Failed to save counted match");
+
+                eprintln!("[MATCH_RESULT] Room '{}' finished, winner: {:?}", archived_match.room_id, archived_match.winner);
+                let mut standings = Vec::new();
+                for player in archived_match.players() {
+                    let standing = self.state.leaderboard.get(&player.chain_id)
+                        .await
+                        .expect("Failed to load standing")
+                        .unwrap_or_else(|| PlayerStanding::new(player));
+                    standings.push((player, standing));
+                }
+
+                // Everyone is rated against the other team's pre-match ratings
+                let team_rating = |team: Team| {
+                    let ratings: Vec<SkillRating> = standings.iter()
+                        .filter(|(player, _)| player.team == team)
+                        .map(|(_, standing)| standing.skill)
+                        .collect();
+                    SkillRating::composite(&ratings)
+                };
+                let blue_rating = team_rating(Team::Blue);
+                let red_rating = team_rating(Team::Red);
+                let rating_system = self.runtime.application_parameters().rating_system;
+
+                for (player, mut standing) in standings {
+                    let opponent = match player.team {
+                        Team::Blue => red_rating,
+                        Team::Red => blue_rating,
+                    };
+                    standing.record_match(&archived_match, player, opponent, rating_system);
+                    eprintln!("[MATCH_RESULT] {} rating is now {}", player.chain_id, standing.skill.rating.round());
+                    self.state.leaderboard
+                        .insert(&player.chain_id, standing)
+                        .expect("Failed to save standing");
+                }
+            }
+
+            CrossChainMessage::UnregisterLobby { room_id } => {
+                if !self.is_directory_chain() {
+                    eprintln!("[UNREGISTER_LOBBY] ERROR: This chain is not the lobby directory");
+                    return;
+                }
+
+                let listing = self.state.lobby_directory.get(&room_id)
+                    .await
+                    .expect("Failed to load lobby listing");
+
+                if let Some(listing) = listing {
+                    if !self.authenticate_origin("UnregisterLobby", &room_id, &listing.host_chain_id) {
+                        return;
+                    }
+
+                    eprintln!("[UNREGISTER_LOBBY] Room '{}' removed from directory", room_id);
+                    self.state.lobby_directory
+                        .remove(&room_id)
+                        .expect("Failed to remove lobby listing");
+                }
+            }
+
+            CrossChainMessage::RematchRequest { room_id, swap_sides } => {
+                eprintln!("[REMATCH_REQUEST] Received rematch request for room '{}' (swap sides: {})", room_id, swap_sides);
+
+                if let Some(room) = self.load_room(&room_id).await {
+                    let Some(player) = self.authenticate_member("RematchRequest", &room) else {
+                        return;
+                    };
+                    if !self.authenticate_host_chain("RematchRequest", &room) {
+                        return;
+                    }
+
+                    if room.match_state != MatchState::Finished {
+                        eprintln!("[REMATCH_REQUEST] ERROR: Match in room '{}' is not finished", room_id);
+                        return;
+                    }
+
+                    self.request_rematch(room, &player.chain_id, swap_sides);
+                }
+            }
+
+            CrossChainMessage::RematchAccept { room_id } => {
+                eprintln!("[REMATCH_ACCEPT] Received rematch acceptance for room '{}'", room_id);
+
+                if let Some(room) = self.load_room(&room_id).await {
+                    let Some(player) = self.authenticate_member("RematchAccept", &room) else {
+                        return;
+                    };
+                    if !self.authenticate_host_chain("RematchAccept", &room) {
+                        return;
+                    }
+
+                    if room.match_state != MatchState::Finished || room.rematch.is_none() {
+                        eprintln!("[REMATCH_ACCEPT] ERROR: No rematch pending in room '{}'", room_id);
+                        return;
+                    }
+
+                    self.accept_rematch(room, &player.chain_id);
                 }
             }
         }
     }
 
+    async fn process_streams(&mut self, updates: Vec<StreamUpdate>) {
+        let app_id = GenericApplicationId::User(self.runtime.application_id().forget_abi());
+
+        for update in updates {
+            if update.stream_id.application_id != app_id {
+                continue;
+            }
+            let Some(room_id) = GameRoom::room_id_from_stream_name(&update.stream_id.stream_name.0) else {
+                continue;
+            };
+            let Some(mut room) = self.load_room(&room_id).await else {
+                eprintln!("[STREAMS] Ignoring events for unknown room '{}'", room_id);
+                continue;
+            };
+
+            // Only the host publishes the room's authoritative events
+            if update.chain_id.to_string() != room.host_chain_id {
+                continue;
+            }
+
+            for index in update.new_indices() {
+                let room_event = self.runtime.read_event(update.chain_id, update.stream_id.stream_name.clone(), index);
+
+                // Already reflected here (our own events, or included in a state sync)
+                if room_event.room_version <= room.room_version {
+                    continue;
+                }
+                // Missed events cannot be replayed; the full state replaces them
+                if room_event.room_version > room.room_version + 1 {
+                    self.request_state_sync(&room).await;
+                    break;
+                }
+
+                let event = room_event.event;
+                eprintln!("[STREAMS] Room '{}' version {}: {:?}", room_id, room_event.room_version, event);
+                room.apply_event(&event);
+                room.room_version = room_event.room_version;
+
+                if let HoverWarsEvent::MatchEnded { timestamp, .. } = &event {
+                    self.record_match_end(&room, timestamp.clone());
+                }
+            }
+
+            self.save_room(&room);
+        }
+    }
+
     async fn store(mut self) {
         self.state.save().await.expect("Failed to save state");
     }
@@ -4,14 +4,19 @@
 /*! ABI of the HoverWars Game Application */
 
 use async_graphql::{Request, Response};
-use linera_sdk::linera_base_types::{ContractAbi, ServiceAbi};
+use linera_sdk::linera_base_types::{BcsHashable, ChainId, ContractAbi, CryptoHash, ServiceAbi};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod rating;
+
+use rating::{Fixed, RatingSystem, SkillRating};
 
 pub struct HoverWarsAbi;
 
 impl ContractAbi for HoverWarsAbi {
     type Operation = Operation;
-    type Response = ();
+    type Response = Result<OperationResponse, HoverWarsError>;
 }
 
 impl ServiceAbi for HoverWarsAbi {
@@ -19,19 +24,158 @@ impl ServiceAbi for HoverWarsAbi {
     type QueryResponse = Response;
 }
 
+/// Prefix of the per-room event stream names
+pub const ROOM_STREAM_PREFIX: &str = "game_events_";
+
+/// Largest number of players allowed on one team
+pub const MAX_TEAM_SIZE: u32 = 8;
+
+/// Time players have to ready up once the rosters are full
+pub const READY_CHECK_DURATION_MICROS: u64 = 60_000_000;
+
+/// Time a team has to report a round once the other team reported it, however long the
+/// round lasted; a team still silent after it loses the match when the other team claims a timeout win
+pub const REPORT_GRACE_MICROS: u64 = 120_000_000;
+
+/// Longest round a report may claim (in seconds)
+pub const MAX_ROUND_DURATION_SECS: u32 = 900;
+/// Time a destroyed hovercraft waits before respawning (in seconds)
+pub const RESPAWN_DELAY_SECS: u32 = 10;
+/// Health of a fresh hovercraft
+pub const HOVERCRAFT_HEALTH: u32 = 100;
+
+/// Longest display name a player profile accepts (in characters)
+pub const MAX_DISPLAY_NAME_LEN: usize = 32;
+
+/// Rating difference the matchmaking queue accepts for a player who just entered it
+pub const MATCHMAKING_BASE_WINDOW: u32 = 100;
+/// Rating points the acceptable difference grows by per second spent in the queue
+pub const MATCHMAKING_WINDOW_GROWTH_PER_SEC: u32 = 5;
+/// Largest rating difference the matchmaking queue ever accepts
+pub const MATCHMAKING_MAX_WINDOW: u32 = 1000;
+
+/// Rejected messages a chain keeps in its security log; older entries are dropped
+pub const SECURITY_LOG_CAPACITY: u32 = 500;
+
+// Application parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HoverWarsParameters {
+    /// Chain keeping the public lobby directory (None disables lobby registration)
+    pub directory_chain_id: Option<ChainId>,
+    /// Rules used by lobbies that don't override them
+    #[serde(default)]
+    pub default_rules: MatchRules,
+    /// Chain aggregating finished matches into the global leaderboard (None disables reporting)
+    #[serde(default)]
+    pub leaderboard_chain_id: Option<ChainId>,
+    /// Rating system used by the leaderboard chain
+    #[serde(default)]
+    pub rating_system: RatingSystem,
+    /// Chain running the matchmaking queue (None disables matchmaking)
+    #[serde(default)]
+    pub matchmaking_chain_id: Option<ChainId>,
+}
+
+// Draw scoring policy enum
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum DrawScoring {
+    /// Both teams score a point
+    #[default]
+    BothScore,
+    /// Nobody scores
+    NoScore,
+}
+
+// Tie-break rule enum (applied when regulation ends with equal scores)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Keep playing until one team leads
+    #[default]
+    SuddenDeath,
+    /// End the match without a winner
+    Draw,
+}
+
+// Match rules
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject, PartialEq, Eq)]
+#[graphql(rename_fields = "camelCase", input_name = "MatchRulesInput")]
+pub struct MatchRules {
+    /// Round wins needed to take the match
+    pub rounds_to_win: u32,
+    /// Maximum number of regular rounds (None for no limit)
+    pub max_rounds: Option<u32>,
+    pub draw_scoring: DrawScoring,
+    pub tie_break: TieBreak,
+    /// Players per team; the match starts once both rosters are full
+    pub team_size: u32,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            rounds_to_win: 3,
+            max_rounds: None,
+            draw_scoring: DrawScoring::BothScore,
+            tie_break: TieBreak::SuddenDeath,
+            team_size: 1,
+        }
+    }
+}
+
+impl MatchRules {
+    /// Check that the rules describe a playable match
+    pub fn validate(&self) -> Result<(), HoverWarsError> {
+        if self.rounds_to_win == 0 {
+            return Err(HoverWarsError::InvalidRules("rounds to win must be at least 1".to_string()));
+        }
+        if self.max_rounds == Some(0) {
+            return Err(HoverWarsError::InvalidRules("max rounds must be at least 1".to_string()));
+        }
+        if self.team_size == 0 || self.team_size > MAX_TEAM_SIZE {
+            return Err(HoverWarsError::InvalidRules(format!("team size must be between 1 and {}", MAX_TEAM_SIZE)));
+        }
+        Ok(())
+    }
+}
+
 // Team enum
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
 pub enum Team {
+    #[default]
     Blue,
     Red,
 }
 
-impl Default for Team {
-    fn default() -> Self {
-        Team::Blue
+impl Team {
+    pub fn opponent(self) -> Team {
+        match self {
+            Team::Blue => Team::Red,
+            Team::Red => Team::Blue,
+        }
+    }
+
+    /// Round result of this team winning the round
+    pub fn round_win(self) -> RoundResult {
+        match self {
+            Team::Blue => RoundResult::BlueWin,
+            Team::Red => RoundResult::RedWin,
+        }
     }
 }
 
+// What happens to a room when its host leaves
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum HostHandover {
+    /// Delete the room on every chain
+    #[default]
+    CloseRoom,
+    /// Hand the room, including its history, over to a remaining player. A match in play goes on
+    /// if the host's team keeps a player and is forfeited otherwise
+    MigrateHost,
+    /// Award a match in play to the other team, then hand the room over
+    Forfeit,
+}
+
 // Player structure
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
@@ -39,23 +183,22 @@ pub struct Player {
     pub chain_id: String,
     pub name: String,
     pub team: Team,
-    pub score: u32,
+    /// Whether the player confirmed the ready check
+    pub ready: bool,
 }
 
 // Match state enum
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
 pub enum MatchState {
+    #[default]
     WaitingForOpponent,
+    ReadyCheck,
     InProgress,
+    RoundDisputed,
+    Overtime,
     Finished,
 }
 
-impl Default for MatchState {
-    fn default() -> Self {
-        MatchState::WaitingForOpponent
-    }
-}
-
 // Round result enum
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
 pub enum RoundResult {
@@ -64,6 +207,163 @@ pub enum RoundResult {
     Draw,
 }
 
+// How a round came to an end
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum RoundEndReason {
+    /// A team delivered the oil to its base
+    #[default]
+    OilDelivered,
+    /// The round clock ran out
+    TimeExpired,
+}
+
+// One player's performance in a round
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject, PartialEq, Eq)]
+#[graphql(rename_fields = "camelCase", input_name = "PlayerRoundStatsInput")]
+pub struct PlayerRoundStats {
+    pub chain_id: String,
+    pub kills: u32,
+    pub deaths: u32,
+    pub damage_dealt: u32,
+    /// Times the player picked up the oil
+    pub oil_pickups: u32,
+    /// Times the player delivered the oil
+    pub oil_deliveries: u32,
+}
+
+// Statistics of a round, as reported by a team for its own players
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject, PartialEq, Eq)]
+#[graphql(rename_fields = "camelCase", input_name = "RoundStatsInput")]
+pub struct RoundStats {
+    pub duration_secs: u32,
+    pub end_reason: RoundEndReason,
+    pub players: Vec<PlayerRoundStats>,
+}
+
+impl RoundStats {
+    /// Check that a team's report of a round it played with the given result is plausible
+    pub fn validate(&self, room: &GameRoom, team: Team, result: RoundResult) -> Result<(), HoverWarsError> {
+        let invalid = |reason: String| Err(HoverWarsError::InvalidRoundStats(reason));
+
+        if self.duration_secs == 0 || self.duration_secs > MAX_ROUND_DURATION_SECS {
+            return invalid(format!("round duration must be between 1 and {} seconds", MAX_ROUND_DURATION_SECS));
+        }
+        if self.end_reason == RoundEndReason::OilDelivered && result == RoundResult::Draw {
+            return invalid("a round decided by an oil delivery cannot be a draw".to_string());
+        }
+
+        // Nobody can be destroyed more often than the respawn delay allows
+        let lives = self.duration_secs / RESPAWN_DELAY_SECS + 1;
+        let opponents = room.roster(team.opponent()).len() as u32;
+
+        for (index, stats) in self.players.iter().enumerate() {
+            if room.team_of(&stats.chain_id) != Some(team) {
+                return invalid(format!("{} is not on the {:?} team", stats.chain_id, team));
+            }
+            if self.players[..index].iter().any(|other| other.chain_id == stats.chain_id) {
+                return invalid(format!("{} is reported twice", stats.chain_id));
+            }
+            if stats.deaths > lives || stats.oil_pickups > lives {
+                return invalid(format!("{} cannot die or pick up the oil more than {} times", stats.chain_id, lives));
+            }
+            if stats.kills > opponents * lives {
+                return invalid(format!("{} cannot score more than {} kills", stats.chain_id, opponents * lives));
+            }
+            if stats.damage_dealt > opponents * lives * HOVERCRAFT_HEALTH {
+                return invalid(format!("{} cannot deal more than {} damage", stats.chain_id, opponents * lives * HOVERCRAFT_HEALTH));
+            }
+            if stats.oil_deliveries > stats.oil_pickups {
+                return invalid(format!("{} delivered the oil more often than they picked it up", stats.chain_id));
+            }
+        }
+
+        // A delivery ends the round in the delivering team's favour
+        let deliveries: u32 = self.players.iter().map(|stats| stats.oil_deliveries).sum();
+        let team_won = result == team.round_win();
+        if deliveries > 1 || (deliveries == 1 && !(team_won && self.end_reason == RoundEndReason::OilDelivered)) {
+            return invalid("only the winner of a round decided by an oil delivery delivers the oil, once".to_string());
+        }
+        Ok(())
+    }
+
+    /// Statistics of a confirmed round from both teams' reports: every player's own team's figures,
+    /// the longer duration and the round winner's end reason (Blue's on a draw)
+    pub fn combine(blue: RoundStats, red: RoundStats, result: RoundResult) -> RoundStats {
+        let end_reason = if result == RoundResult::RedWin { red.end_reason } else { blue.end_reason };
+        RoundStats {
+            duration_secs: blue.duration_secs.max(red.duration_secs),
+            end_reason,
+            players: blue.players.into_iter().chain(red.players).collect(),
+        }
+    }
+}
+
+// A player's statistics summed over a match
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct PlayerMatchStats {
+    pub chain_id: String,
+    pub name: String,
+    pub team: Team,
+    pub kills: u32,
+    pub deaths: u32,
+    pub damage_dealt: u32,
+    pub oil_pickups: u32,
+    pub oil_deliveries: u32,
+}
+
+// Statistics of a whole match, summed over its rounds
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct MatchStats {
+    pub rounds_played: u32,
+    pub total_duration_secs: u32,
+    pub rounds_ended_by_delivery: u32,
+    /// Players on the final rosters, Blue first
+    pub players: Vec<PlayerMatchStats>,
+}
+
+impl MatchStats {
+    pub fn from_rounds<'a>(players: impl Iterator<Item = &'a Player>, rounds: &[RoundHistoryEntry]) -> Self {
+        let players = players
+            .map(|player| {
+                let mut totals = PlayerMatchStats {
+                    chain_id: player.chain_id.clone(),
+                    name: player.name.clone(),
+                    team: player.team,
+                    ..Default::default()
+                };
+                for stats in rounds.iter().flat_map(|round| &round.stats.players).filter(|stats| stats.chain_id == player.chain_id) {
+                    totals.kills += stats.kills;
+                    totals.deaths += stats.deaths;
+                    totals.damage_dealt += stats.damage_dealt;
+                    totals.oil_pickups += stats.oil_pickups;
+                    totals.oil_deliveries += stats.oil_deliveries;
+                }
+                totals
+            })
+            .collect();
+
+        MatchStats {
+            rounds_played: rounds.len() as u32,
+            total_duration_secs: rounds.iter().map(|round| round.stats.duration_secs).sum(),
+            rounds_ended_by_delivery: rounds.iter().filter(|round| round.stats.end_reason == RoundEndReason::OilDelivered).count() as u32,
+            players,
+        }
+    }
+}
+
+// Outcome of submitting a round report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoundConfirmation {
+    /// Still waiting for the other team's report
+    Pending,
+    /// Both teams reported the same result
+    Confirmed { result: RoundResult, stats: RoundStats },
+    /// The teams reported different results
+    Disputed { blue_result: RoundResult, red_result: RoundResult },
+}
+
 // Round history entry
 #[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 #[graphql(rename_fields = "camelCase")]
@@ -73,6 +373,89 @@ pub struct RoundHistoryEntry {
     pub blue_score_after: u32,
     pub red_score_after: u32,
     pub timestamp: String,
+    /// Sudden-death round played after regulation ended level
+    pub overtime: bool,
+    pub stats: RoundStats,
+}
+
+// Rematch proposed after a finished match
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct RematchProposal {
+    pub requested_by: String,
+    /// Whether Blue and Red switch rosters for the rematch
+    pub swap_sides: bool,
+    /// Chains that agreed to the rematch (the requester included)
+    pub accepted_by: Vec<String>,
+}
+
+// Lobby visibility enum
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum LobbyVisibility {
+    /// Listed in the lobby directory; anyone can join
+    #[default]
+    Public,
+    /// Unlisted; only invited chains can join
+    Private,
+}
+
+/// Invite code as hashed for private lobbies
+#[derive(Serialize, Deserialize)]
+struct InviteCode(String);
+
+impl BcsHashable<'_> for InviteCode {}
+
+/// Hash under which a private lobby stores its invite code
+pub fn hash_invite_code(invite_code: &str) -> String {
+    CryptoHash::new(&InviteCode(invite_code.to_string())).to_string()
+}
+
+// Who may join a lobby
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject, PartialEq, Eq)]
+#[graphql(rename_fields = "camelCase")]
+pub struct LobbyAccess {
+    pub visibility: LobbyVisibility,
+    /// Hash of the invite code accepted by a private lobby (see `hash_invite_code`)
+    #[graphql(skip)]
+    pub invite_code_hash: Option<String>,
+    /// Chains a private lobby admits without an invite code
+    pub allowed_chain_ids: Vec<String>,
+}
+
+impl LobbyAccess {
+    /// Check that a private lobby can be joined at all
+    pub fn validate(&self) -> Result<(), HoverWarsError> {
+        if self.visibility == LobbyVisibility::Private && self.invite_code_hash.is_none() && self.allowed_chain_ids.is_empty() {
+            return Err(HoverWarsError::InvalidLobbyAccess("a private lobby needs an invite code or allowed chains".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Whether a chain presenting `invite_code` may join
+    pub fn admits(&self, chain_id: &str, invite_code: Option<&str>) -> bool {
+        match self.visibility {
+            LobbyVisibility::Public => true,
+            LobbyVisibility::Private => {
+                self.allowed_chain_ids.iter().any(|allowed| allowed == chain_id)
+                    || self.invite_code_hash.as_ref().is_some_and(|hash| {
+                        invite_code.is_some_and(|code| hash_invite_code(code) == *hash)
+                    })
+            }
+        }
+    }
+}
+
+// Match given up before it was played out
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject, PartialEq, Eq)]
+#[graphql(rename_fields = "camelCase")]
+pub struct ForfeitRecord {
+    /// Team that gave the match up
+    pub team: Team,
+    /// Player who gave it up for their team, or who claimed the win over a silent team
+    pub player_chain_id: String,
+    pub reason: String,
+    /// Round in play when the match was given up
+    pub round_number: u32,
 }
 
 // Game room structure
@@ -81,22 +464,510 @@ pub struct RoundHistoryEntry {
 pub struct GameRoom {
     pub room_id: String,
     pub host_chain_id: String,
-    pub blue_player: Player,
-    pub red_player: Option<Player>,
+    pub blue_team: Vec<Player>,
+    pub red_team: Vec<Player>,
+    pub blue_score: u32,
+    pub red_score: u32,
     pub match_state: MatchState,
+    /// End of the current ready check (microseconds); unready players restart the check after it
+    pub ready_deadline_micros: Option<u64>,
     pub current_round: u32,
+    /// Deadline for the other team's report once one team reported the current round (microseconds)
+    pub report_deadline_micros: Option<u64>,
+    pub round_history: Vec<RoundHistoryEntry>,
+    pub winner: Option<Team>,
+    pub created_at: String,
+    pub access: LobbyAccess,
+    /// Chains the host banned from rejoining
+    pub banned_chain_ids: Vec<String>,
+    /// When the current match started
+    pub started_at: Option<String>,
+    pub rules: MatchRules,
+    /// Whether the match went to sudden-death overtime
+    pub overtime: bool,
+    /// How the match was forfeited, if it was
+    pub forfeit: Option<ForfeitRecord>,
+    /// Blue team's proposed result for the current round
+    pub blue_report: Option<RoundResult>,
+    /// Red team's proposed result for the current round
+    pub red_report: Option<RoundResult>,
+    /// Blue team's statistics for the current round, sent with its report
+    pub blue_stats: Option<RoundStats>,
+    /// Red team's statistics for the current round, sent with its report
+    pub red_stats: Option<RoundStats>,
+    /// Pending rematch once the match is finished
+    pub rematch: Option<RematchProposal>,
+    /// Number of events the room went through (carried by every event, survives host migration)
+    pub room_version: u64,
+}
+
+// Finished match kept in a participant chain's match archive
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase", complex)]
+pub struct ArchivedMatch {
+    /// Position in this chain's match archive
+    pub match_id: u32,
+    pub room_id: String,
+    pub host_chain_id: String,
+    pub blue_team: Vec<Player>,
+    pub red_team: Vec<Player>,
+    pub rules: MatchRules,
     pub round_history: Vec<RoundHistoryEntry>,
     pub winner: Option<Team>,
+    pub blue_score: u32,
+    pub red_score: u32,
+    pub overtime: bool,
+    pub forfeit: Option<ForfeitRecord>,
+    /// When the room was created
     pub created_at: String,
+    pub started_at: Option<String>,
+    pub finished_at: String,
+}
+
+#[async_graphql::ComplexObject]
+impl ArchivedMatch {
+    async fn stats(&self) -> MatchStats {
+        MatchStats::from_rounds(self.players(), &self.round_history)
+    }
+}
+
+impl ArchivedMatch {
+    /// All players of the match, Blue first
+    pub fn players(&self) -> impl Iterator<Item = &Player> {
+        self.blue_team.iter().chain(self.red_team.iter())
+    }
+
+    pub fn has_player(&self, chain_id: &str) -> bool {
+        self.players().any(|p| p.chain_id == chain_id)
+    }
+
+    /// Identifies the match across chains (match IDs are per chain)
+    pub fn result_key(&self) -> String {
+        format!("{}/{}/{}", self.host_chain_id, self.room_id, self.started_at.as_deref().unwrap_or_default())
+    }
+
+    /// Number of rounds a team won
+    pub fn rounds_won(&self, team: Team) -> u32 {
+        self.round_history.iter().filter(|round| round.result == team.round_win()).count() as u32
+    }
+
+    pub fn rounds_drawn(&self) -> u32 {
+        self.round_history.iter().filter(|round| round.result == RoundResult::Draw).count() as u32
+    }
+}
+
+// Career statistics of the player on this chain
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct PlayerProfile {
+    pub display_name: String,
+    pub matches_played: u32,
+    pub matches_won: u32,
+    pub matches_lost: u32,
+    pub matches_drawn: u32,
+    pub rounds_won: u32,
+    pub rounds_drawn: u32,
+    pub current_win_streak: u32,
+    pub best_win_streak: u32,
+    pub first_played_at: Option<String>,
+    pub last_played_at: Option<String>,
+}
+
+impl PlayerProfile {
+    /// Change the display name, rejecting blank or overly long names
+    pub fn set_display_name(&mut self, display_name: &str) -> Result<(), HoverWarsError> {
+        let display_name = display_name.trim();
+        if display_name.is_empty() {
+            return Err(HoverWarsError::InvalidDisplayName("display name cannot be empty".to_string()));
+        }
+        if display_name.chars().count() > MAX_DISPLAY_NAME_LEN {
+            return Err(HoverWarsError::InvalidDisplayName(format!("display name cannot exceed {} characters", MAX_DISPLAY_NAME_LEN)));
+        }
+        self.display_name = display_name.to_string();
+        Ok(())
+    }
+
+    /// Count a finished match played as `player`
+    pub fn record_match(&mut self, archived: &ArchivedMatch, player: &Player) {
+        let team = player.team;
+        if self.display_name.is_empty() {
+            self.display_name = player.name.clone();
+        }
+
+        self.matches_played += 1;
+        match archived.winner {
+            Some(winner) if winner == team => {
+                self.matches_won += 1;
+                self.current_win_streak += 1;
+                self.best_win_streak = self.best_win_streak.max(self.current_win_streak);
+            }
+            Some(_) => {
+                self.matches_lost += 1;
+                self.current_win_streak = 0;
+            }
+            None => {
+                self.matches_drawn += 1;
+                self.current_win_streak = 0;
+            }
+        }
+
+        self.rounds_won += archived.rounds_won(team);
+        self.rounds_drawn += archived.rounds_drawn();
+
+        if self.first_played_at.is_none() {
+            self.first_played_at = Some(archived.finished_at.clone());
+        }
+        self.last_played_at = Some(archived.finished_at.clone());
+    }
+}
+
+// Rating change caused by one match
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct RatingChange {
+    pub room_id: String,
+    pub rating_before: i64,
+    pub rating_after: i64,
+    pub rating_delta: i64,
+    pub deviation_after: i64,
+    pub timestamp: String,
+}
+
+// Player standing on the leaderboard chain
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase", complex)]
+pub struct PlayerStanding {
+    pub chain_id: String,
+    /// Name used in the player's latest match
+    pub name: String,
+    pub matches_played: u32,
+    pub matches_won: u32,
+    pub matches_lost: u32,
+    pub matches_drawn: u32,
+    pub rounds_won: u32,
+    pub last_played_at: String,
+    /// Exact skill estimate (exposed rounded as `rating` and `deviation`)
+    #[graphql(skip)]
+    pub skill: SkillRating,
+    /// Rating changes, oldest first
+    pub rating_history: Vec<RatingChange>,
+}
+
+#[async_graphql::ComplexObject]
+impl PlayerStanding {
+    async fn rating(&self) -> i64 {
+        self.skill.rating.round()
+    }
+
+    async fn deviation(&self) -> i64 {
+        self.skill.deviation.round()
+    }
+}
+
+impl PlayerStanding {
+    pub fn new(player: &Player) -> Self {
+        Self {
+            chain_id: player.chain_id.clone(),
+            name: player.name.clone(),
+            matches_played: 0,
+            matches_won: 0,
+            matches_lost: 0,
+            matches_drawn: 0,
+            rounds_won: 0,
+            last_played_at: String::new(),
+            skill: SkillRating::default(),
+            rating_history: Vec::new(),
+        }
+    }
+
+    /// Count a finished match played as `player` against a team rated as `opponent`
+    pub fn record_match(&mut self, archived: &ArchivedMatch, player: &Player, opponent: SkillRating, rating_system: RatingSystem) {
+        self.name = player.name.clone();
+        self.matches_played += 1;
+        let score = match archived.winner {
+            Some(winner) if winner == player.team => {
+                self.matches_won += 1;
+                Fixed::ONE
+            }
+            Some(_) => {
+                self.matches_lost += 1;
+                Fixed::ZERO
+            }
+            None => {
+                self.matches_drawn += 1;
+                Fixed::HALF
+            }
+        };
+        self.rounds_won += archived.rounds_won(player.team);
+        self.last_played_at = archived.finished_at.clone();
+
+        let rating_before = self.skill.rating.round();
+        self.skill = self.skill.update(rating_system, opponent, score);
+        let rating_after = self.skill.rating.round();
+        self.rating_history.push(RatingChange {
+            room_id: archived.room_id.clone(),
+            rating_before,
+            rating_after,
+            rating_delta: rating_after - rating_before,
+            deviation_after: self.skill.deviation.round(),
+            timestamp: archived.finished_at.clone(),
+        });
+    }
+}
+
+// Public lobby directory entry
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct LobbyListing {
+    pub room_id: String,
+    pub host_chain_id: String,
+    pub host_name: String,
+    pub match_state: MatchState,
+    pub created_at: String,
+}
+
+// Join request status enum
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum JoinStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+// Join request sent from this chain
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct JoinAttempt {
+    pub room_id: String,
+    pub host_chain_id: String,
+    pub status: JoinStatus,
+    pub rejection_reason: Option<String>,
+    pub requested_at: String,
+}
+
+// Matchmaking status enum
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::Enum, PartialEq, Eq)]
+pub enum QueueStatus {
+    /// Waiting for an opponent
+    Queued,
+    /// Paired with an opponent; the lobby is being set up
+    Matched,
+}
+
+// Matchmaking ticket of the player on this chain
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct QueueTicket {
+    pub mode: String,
+    pub rating: u32,
+    pub player_name: String,
+    pub status: QueueStatus,
+    /// Room the match is played in, once known
+    pub room_id: Option<String>,
+    pub entered_at: String,
+}
+
+// Player waiting in the queue (matchmaking chain only)
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct QueueEntry {
+    pub chain_id: String,
+    pub player_name: String,
+    pub mode: String,
+    pub rating: u32,
+    /// When the player entered the queue (microseconds)
+    pub entered_at_micros: u64,
+}
+
+impl QueueEntry {
+    /// Largest rating difference this player accepts, widening the longer they wait
+    pub fn rating_window(&self, now_micros: u64) -> u32 {
+        let waited_secs = now_micros.saturating_sub(self.entered_at_micros) / 1_000_000;
+        let growth = waited_secs.saturating_mul(MATCHMAKING_WINDOW_GROWTH_PER_SEC as u64);
+        (MATCHMAKING_BASE_WINDOW as u64)
+            .saturating_add(growth)
+            .min(MATCHMAKING_MAX_WINDOW as u64) as u32
+    }
+
+    /// Whether two players can be paired at `now_micros`
+    pub fn accepts(&self, other: &QueueEntry, now_micros: u64) -> bool {
+        let window = self.rating_window(now_micros).max(other.rating_window(now_micros));
+        self.mode == other.mode && self.chain_id != other.chain_id && self.rating.abs_diff(other.rating) <= window
+    }
+}
+
+// Cross-chain message rejected because of an unexpected origin
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct RejectedMessage {
+    /// Position in this chain's security log
+    pub entry_id: u32,
+    pub message_kind: String,
+    pub room_id: String,
+    pub origin_chain_id: Option<String>,
+    pub expected_chain_id: String,
+    pub timestamp: String,
 }
 
 // Operations
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Operation {
-    CreateLobby { host_name: String },
-    JoinLobby { host_chain_id: String, player_name: String },
-    ReportRoundResult { result: RoundResult },
-    LeaveLobby,
+    CreateLobby { host_name: String, rules: Option<MatchRules>, access: LobbyAccess },
+    JoinLobby { host_chain_id: String, room_id: String, player_name: String, invite_code: Option<String> },
+    ReportRoundResult { room_id: String, round_number: u32, result: RoundResult, stats: RoundStats },
+    LeaveLobby { room_id: String, handover: HostHandover },
+    SetReady { room_id: String, ready: bool },
+    SetProfile { display_name: String },
+    EnterQueue { mode: String, rating: u32, player_name: String },
+    LeaveQueue,
+    RequestRematch { room_id: String, swap_sides: bool },
+    AcceptRematch { room_id: String },
+    KickPlayer { room_id: String, chain_id: String, ban: bool },
+    Concede { room_id: String, reason: Option<String> },
+    ClaimTimeoutWin { room_id: String },
+}
+
+impl Operation {
+    /// Name of the operation, as used to look up its outcome
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Operation::CreateLobby { .. } => "CreateLobby",
+            Operation::JoinLobby { .. } => "JoinLobby",
+            Operation::ReportRoundResult { .. } => "ReportRoundResult",
+            Operation::LeaveLobby { .. } => "LeaveLobby",
+            Operation::SetReady { .. } => "SetReady",
+            Operation::SetProfile { .. } => "SetProfile",
+            Operation::EnterQueue { .. } => "EnterQueue",
+            Operation::LeaveQueue => "LeaveQueue",
+            Operation::RequestRematch { .. } => "RequestRematch",
+            Operation::AcceptRematch { .. } => "AcceptRematch",
+            Operation::KickPlayer { .. } => "KickPlayer",
+            Operation::Concede { .. } => "Concede",
+            Operation::ClaimTimeoutWin { .. } => "ClaimTimeoutWin",
+        }
+    }
+}
+
+// Errors returned by operations
+#[derive(Debug, Clone, Error, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HoverWarsError {
+    #[error("room {0} not found")]
+    RoomNotFound(String),
+    #[error("caller is not a player in this room")]
+    NotInRoom,
+    #[error("match is not in progress")]
+    MatchNotInProgress,
+    #[error("room is full")]
+    RoomFull,
+    #[error("player is already in the room")]
+    AlreadyInRoom,
+    #[error("invalid chain ID: {0}")]
+    InvalidChainId(String),
+    #[error("round {0} was already reported")]
+    AlreadyReported(u32),
+    #[error("report is for round {reported}, but the current round is {current}")]
+    WrongRound { reported: u32, current: u32 },
+    #[error("invalid match rules: {0}")]
+    InvalidRules(String),
+    #[error("room is not in a ready check")]
+    NotInReadyCheck,
+    #[error("match is not finished")]
+    MatchNotFinished,
+    #[error("no rematch was requested")]
+    NoRematchRequested,
+    #[error("invalid display name: {0}")]
+    InvalidDisplayName(String),
+    #[error("no matchmaking chain is configured")]
+    MatchmakingDisabled,
+    #[error("already in the matchmaking queue")]
+    AlreadyQueued,
+    #[error("not in the matchmaking queue")]
+    NotQueued,
+    #[error("invalid lobby access: {0}")]
+    InvalidLobbyAccess(String),
+    #[error("not invited to this private lobby")]
+    NotInvited,
+    #[error("only the host can do this")]
+    NotHost,
+    #[error("player {0} is not in this room")]
+    PlayerNotFound(String),
+    #[error("banned from this room")]
+    Banned,
+    #[error("not possible while a match is in progress")]
+    MatchInProgress,
+    #[error("the report deadline has not passed yet")]
+    DeadlineNotReached,
+    #[error("implausible round statistics: {0}")]
+    InvalidRoundStats(String),
+    #[error("a timeout win needs your team's report of the round and none from the other team")]
+    NoTimeoutToClaim,
+}
+
+// Successful operation outcome
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct OperationResponse {
+    /// Room the operation acted on (None for operations not tied to a room)
+    pub room_id: Option<String>,
+    /// Local view of the room after the operation (None if the room is not stored on this chain)
+    pub match_state: Option<MatchState>,
+    pub current_round: Option<u32>,
+    pub match_ended: bool,
+}
+
+// Result of an operation executed on this chain, kept for clients (mutations only schedule operations)
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(rename_fields = "camelCase")]
+pub struct OperationOutcome {
+    /// Operation name, e.g. "CreateLobby"
+    pub operation: String,
+    /// Number of operations executed on this chain before this one
+    pub sequence: u64,
+    /// Success data (None if the operation failed)
+    pub response: Option<OperationResponse>,
+    /// Why the operation failed (None if it succeeded)
+    pub error: Option<String>,
+    pub timestamp: String,
+}
+
+impl OperationResponse {
+    /// Response describing a room stored on this chain
+    pub fn for_room(room: &GameRoom) -> Self {
+        Self {
+            room_id: Some(room.room_id.clone()),
+            match_state: Some(room.match_state),
+            current_round: Some(room.current_round),
+            match_ended: room.match_state == MatchState::Finished,
+        }
+    }
+
+    /// Response for a room not (or no longer) stored on this chain
+    pub fn for_room_id(room_id: String) -> Self {
+        Self {
+            room_id: Some(room_id),
+            match_state: None,
+            current_round: None,
+            match_ended: false,
+        }
+    }
+
+    /// Response for an operation that does not involve a room
+    pub fn without_room() -> Self {
+        Self {
+            room_id: None,
+            match_state: None,
+            current_round: None,
+            match_ended: false,
+        }
+    }
+}
+
+// Event published on a room's stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomEvent {
+    /// Room version after the event; guests apply events in version order
+    pub room_version: u64,
+    pub event: HoverWarsEvent,
 }
 
 // Events for cross-chain synchronization
@@ -106,9 +977,18 @@ pub enum HoverWarsEvent {
         player: Player, 
         timestamp: String 
     },
+    ReadyCheckStarted {
+        deadline_micros: u64,
+        timestamp: String,
+    },
+    PlayerReady {
+        player_chain_id: String,
+        ready: bool,
+        timestamp: String,
+    },
     MatchStarted { 
-        blue_player_name: String, 
-        red_player_name: String, 
+        blue_team: Vec<String>, 
+        red_team: Vec<String>, 
         timestamp: String 
     },
     RoundCompleted { 
@@ -116,125 +996,575 @@ pub enum HoverWarsEvent {
         result: RoundResult, 
         blue_score: u32, 
         red_score: u32, 
-        timestamp: String 
+        timestamp: String,
+        overtime: bool,
+        stats: RoundStats,
+    },
+    OvertimeStarted {
+        blue_score: u32,
+        red_score: u32,
+        timestamp: String,
+    },
+    RoundDisputed {
+        round_number: u32,
+        blue_result: RoundResult,
+        red_result: RoundResult,
+        timestamp: String,
+    },
+    /// One team reported the current round; the other team has until the deadline to report it too
+    RoundReported {
+        round_number: u32,
+        team: Team,
+        deadline_micros: u64,
+        timestamp: String,
     },
     MatchEnded { 
         winner: Option<Team>, 
         blue_score: u32, 
         red_score: u32, 
-        timestamp: String 
+        timestamp: String,
+        forfeit: Option<ForfeitRecord>,
     },
     PlayerLeft {
         player_chain_id: String,
         timestamp: String,
     },
+    PlayerKicked {
+        player_chain_id: String,
+        banned: bool,
+        timestamp: String,
+    },
+    RematchRequested {
+        requested_by: String,
+        swap_sides: bool,
+        timestamp: String,
+    },
+    RematchAccepted {
+        player_chain_id: String,
+        timestamp: String,
+    },
+    /// A ReadyCheckStarted event follows
+    RematchStarted {
+        swap_sides: bool,
+        timestamp: String,
+    },
 }
 
 // Cross-chain messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CrossChainMessage {
     JoinRequest {
-        player_chain_id: linera_sdk::linera_base_types::ChainId,
+        room_id: String,
+        player_chain_id: ChainId,
         player_name: String,
+        invite_code: Option<String>,
     },
+    JoinAccepted {
+        room_id: String,
+    },
+    JoinRejected {
+        room_id: String,
+        reason: HoverWarsError,
+    },
+    /// Full room state, sent to new players and in reply to `RequestStateSync`
     InitialStateSync {
         room_data: GameRoom,
     },
-    RoundResultNotification {
+    /// Guest missed room events and asks the host for the full state
+    RequestStateSync {
+        room_id: String,
+        known_version: u64,
+    },
+    ReadyUpdate {
+        room_id: String,
+        ready: bool,
+    },
+    RoundReport {
+        room_id: String,
         round_number: u32,
         result: RoundResult,
-        blue_score: u32,
-        red_score: u32,
-        timestamp: String,
-    },
-    MatchEndNotification {
-        winner: Option<Team>,
-        blue_score: u32,
-        red_score: u32,
-        timestamp: String,
+        stats: RoundStats,
     },
     RoomDeleted {
+        room_id: String,
         timestamp: String,
     },
     PlayerLeftNotification {
+        room_id: String,
         player_chain_id: String,
         timestamp: String,
     },
+    /// Leaving host hands the room over; `room_data` is the room before the handover
+    HostTransfer {
+        room_data: GameRoom,
+        new_host_chain_id: String,
+    },
+    /// Guest gives the match up on its team's behalf
+    ConcedeNotification {
+        room_id: String,
+        reason: String,
+    },
+    /// Guest claims the match because the other team missed the report deadline
+    TimeoutClaim {
+        room_id: String,
+    },
+    /// Host removed the receiving chain's player from the room
+    KickedNotification {
+        room_id: String,
+        banned: bool,
+        timestamp: String,
+    },
+    RegisterLobby {
+        listing: LobbyListing,
+    },
+    /// Player asks the matchmaking chain for an opponent
+    EnterQueue {
+        mode: String,
+        rating: u32,
+        player_name: String,
+    },
+    LeaveQueue,
+    /// Matchmaking chain asks one of the paired players to host the match
+    CreateMatchLobby {
+        opponent_chain_id: String,
+    },
+    /// Host tells the matchmaking chain which room the opponent should join
+    MatchLobbyCreated {
+        room_id: String,
+    },
+    /// Host is no longer queued and turns the pairing down
+    MatchDeclined,
+    /// Matchmaking chain sends the other paired player to the host's room
+    JoinMatchLobby {
+        host_chain_id: String,
+        room_id: String,
+    },
+    /// Opponent is no longer queued and turns the host's matchmade room down
+    MatchLobbyDeclined {
+        room_id: String,
+    },
+    /// Finished match reported by its host to the leaderboard chain
+    MatchResult {
+        archived_match: ArchivedMatch,
+    },
+    UnregisterLobby {
+        room_id: String,
+    },
+    RematchRequest {
+        room_id: String,
+        swap_sides: bool,
+    },
+    RematchAccept {
+        room_id: String,
+    },
 }
 
 impl GameRoom {
-    pub fn new(host_chain_id: String, host_name: String, timestamp: String) -> Self {
-        let blue_player = Player {
+    pub fn new(room_id: String, host_chain_id: String, host_name: String, rules: MatchRules, access: LobbyAccess, timestamp: String) -> Self {
+        let host = Player {
             chain_id: host_chain_id.clone(),
             name: host_name,
             team: Team::Blue,
-            score: 0,
+            ready: false,
         };
 
         Self {
-            room_id: timestamp.clone(),
+            room_id,
             host_chain_id,
-            blue_player,
-            red_player: None,
+            blue_team: vec![host],
+            red_team: Vec::new(),
+            blue_score: 0,
+            red_score: 0,
             match_state: MatchState::WaitingForOpponent,
+            ready_deadline_micros: None,
+            report_deadline_micros: None,
             current_round: 0,
             round_history: Vec::new(),
             winner: None,
             created_at: timestamp,
+            access,
+            banned_chain_ids: Vec::new(),
+            started_at: None,
+            rules,
+            overtime: false,
+            forfeit: None,
+            blue_report: None,
+            red_report: None,
+            blue_stats: None,
+            red_stats: None,
+            rematch: None,
+            room_version: 0,
         }
     }
 
-    pub fn add_red_player(&mut self, chain_id: String, name: String) {
-        self.red_player = Some(Player {
-            chain_id,
-            name,
-            team: Team::Red,
-            score: 0,
-        });
+    /// Name of the host
+    pub fn host_name(&self) -> String {
+        self.player(&self.host_chain_id).map(|p| p.name.clone()).unwrap_or_default()
+    }
+
+    pub fn roster(&self, team: Team) -> &Vec<Player> {
+        match team {
+            Team::Blue => &self.blue_team,
+            Team::Red => &self.red_team,
+        }
+    }
+
+    fn roster_mut(&mut self, team: Team) -> &mut Vec<Player> {
+        match team {
+            Team::Blue => &mut self.blue_team,
+            Team::Red => &mut self.red_team,
+        }
+    }
+
+    /// All players in the room, Blue first
+    pub fn players(&self) -> impl Iterator<Item = &Player> {
+        self.blue_team.iter().chain(self.red_team.iter())
+    }
+
+    /// All players except the host
+    pub fn guests(&self) -> impl Iterator<Item = &Player> {
+        self.players().filter(|p| p.chain_id != self.host_chain_id)
+    }
+
+    pub fn player(&self, chain_id: &str) -> Option<&Player> {
+        self.players().find(|p| p.chain_id == chain_id)
+    }
+
+    /// Team a chain plays for, if it is in the room
+    pub fn team_of(&self, chain_id: &str) -> Option<Team> {
+        self.player(chain_id).map(|p| p.team)
+    }
+
+    /// Team with an open slot, preferring the smaller roster (None if the room is full)
+    pub fn open_team(&self) -> Option<Team> {
+        let team_size = self.rules.team_size as usize;
+        if self.red_team.len() < team_size && self.red_team.len() < self.blue_team.len() {
+            Some(Team::Red)
+        } else if self.blue_team.len() < team_size {
+            Some(Team::Blue)
+        } else if self.red_team.len() < team_size {
+            Some(Team::Red)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.open_team().is_none()
+    }
+
+    /// Add a player to the team with an open slot, returning the new player
+    pub fn add_player(&mut self, chain_id: String, name: String) -> Option<Player> {
+        let team = self.open_team()?;
+        let player = Player { chain_id, name, team, ready: false };
+        self.roster_mut(team).push(player.clone());
+        Some(player)
+    }
+
+    /// Ask every player to ready up before the deadline
+    pub fn begin_ready_check(&mut self, deadline_micros: u64) {
+        for player in self.blue_team.iter_mut().chain(self.red_team.iter_mut()) {
+            player.ready = false;
+        }
+        self.match_state = MatchState::ReadyCheck;
+        self.ready_deadline_micros = Some(deadline_micros);
+    }
+
+    pub fn set_ready(&mut self, chain_id: &str, ready: bool) {
+        if let Some(player) = self.blue_team.iter_mut().chain(self.red_team.iter_mut()).find(|p| p.chain_id == chain_id) {
+            player.ready = ready;
+        }
+    }
+
+    pub fn all_ready(&self) -> bool {
+        self.players().all(|p| p.ready)
+    }
+
+    /// Whether the ready check deadline has passed
+    pub fn ready_check_expired(&self, now_micros: u64) -> bool {
+        self.ready_deadline_micros.is_some_and(|deadline| now_micros > deadline)
+    }
+
+    /// Start a fresh match with the current rosters
+    pub fn start_match(&mut self, timestamp: String) {
+        self.started_at = Some(timestamp);
+        self.blue_score = 0;
+        self.red_score = 0;
+        self.round_history.clear();
+        self.winner = None;
         self.match_state = MatchState::InProgress;
+        self.ready_deadline_micros = None;
         self.current_round = 1;
+        self.overtime = false;
+        self.forfeit = None;
+        self.rematch = None;
+        self.clear_round_reports();
+    }
+
+    /// Propose a rematch (replacing any earlier proposal); the requester agrees to it
+    pub fn request_rematch(&mut self, chain_id: &str, swap_sides: bool) {
+        self.rematch = Some(RematchProposal {
+            requested_by: chain_id.to_string(),
+            swap_sides,
+            accepted_by: vec![chain_id.to_string()],
+        });
+    }
+
+    pub fn accept_rematch(&mut self, chain_id: &str) {
+        if let Some(rematch) = self.rematch.as_mut() {
+            if !rematch.accepted_by.iter().any(|c| c == chain_id) {
+                rematch.accepted_by.push(chain_id.to_string());
+            }
+        }
+    }
+
+    /// Whether every player agreed to the pending rematch
+    pub fn rematch_agreed(&self) -> bool {
+        self.rematch.as_ref().is_some_and(|rematch| {
+            self.players().all(|p| rematch.accepted_by.contains(&p.chain_id))
+        })
+    }
+
+    /// Switch the Blue and Red rosters
+    pub fn swap_sides(&mut self) {
+        std::mem::swap(&mut self.blue_team, &mut self.red_team);
+        for player in self.blue_team.iter_mut() {
+            player.team = Team::Blue;
+        }
+        for player in self.red_team.iter_mut() {
+            player.team = Team::Red;
+        }
+    }
+
+    /// Snapshot of the finished match for the match archive
+    pub fn archive(&self, match_id: u32, finished_at: String) -> ArchivedMatch {
+        ArchivedMatch {
+            match_id,
+            room_id: self.room_id.clone(),
+            host_chain_id: self.host_chain_id.clone(),
+            blue_team: self.blue_team.clone(),
+            red_team: self.red_team.clone(),
+            rules: self.rules,
+            round_history: self.round_history.clone(),
+            winner: self.winner,
+            blue_score: self.blue_score,
+            red_score: self.red_score,
+            overtime: self.overtime,
+            forfeit: self.forfeit.clone(),
+            created_at: self.created_at.clone(),
+            started_at: self.started_at.clone(),
+            finished_at,
+        }
+    }
+
+    /// Remove a player. A match in play goes on without them (see `can_continue_without`; a team
+    /// left empty must forfeit first); otherwise the room waits for the rosters to fill again
+    /// and the last match is cleared.
+    pub fn remove_player(&mut self, chain_id: &str) {
+        self.blue_team.retain(|p| p.chain_id != chain_id);
+        self.red_team.retain(|p| p.chain_id != chain_id);
+        if self.accepts_round_reports() {
+            return;
+        }
+        for player in self.blue_team.iter_mut().chain(self.red_team.iter_mut()) {
+            player.ready = false;
+        }
+        self.match_state = MatchState::WaitingForOpponent;
+        self.ready_deadline_micros = None;
+        self.blue_score = 0;
+        self.red_score = 0;
+        self.round_history.clear();
+        self.winner = None;
+        self.forfeit = None;
+        self.current_round = 0;
+        self.overtime = false;
+        self.rematch = None;
+        self.clear_round_reports();
+    }
+
+    /// End a match in play with the other team winning (false if no match is in play)
+    pub fn forfeit_match(&mut self, forfeit: ForfeitRecord) -> bool {
+        if !self.accepts_round_reports() {
+            return false;
+        }
+        self.clear_round_reports();
+        let winner = forfeit.team.opponent();
+        self.forfeit = Some(forfeit);
+        self.finish(Some(winner))
+    }
+
+    /// Make another player the host and drop the previous host from the rosters (see `remove_player`).
+    /// The new host publishes the room on a stream of its own chain, continuing the room version.
+    pub fn migrate_host(&mut self, new_host_chain_id: &str) {
+        let previous_host = std::mem::replace(&mut self.host_chain_id, new_host_chain_id.to_string());
+        self.remove_player(&previous_host);
+    }
+
+    /// Whether the match in play can go on without a player, i.e. their team keeps someone
+    pub fn can_continue_without(&self, chain_id: &str) -> bool {
+        self.team_of(chain_id).is_some_and(|team| self.roster(team).len() > 1)
+    }
+
+    /// Remove a guest on the host's behalf, optionally banning their chain
+    pub fn kick_player(&mut self, chain_id: &str, ban: bool) {
+        self.remove_player(chain_id);
+        if ban && !self.is_banned(chain_id) {
+            self.banned_chain_ids.push(chain_id.to_string());
+        }
+    }
+
+    pub fn is_banned(&self, chain_id: &str) -> bool {
+        self.banned_chain_ids.iter().any(|banned| banned == chain_id)
+    }
+
+    /// Whether the other team's report of the current round is overdue
+    pub fn report_deadline_passed(&self, now_micros: u64) -> bool {
+        self.report_deadline_micros.is_some_and(|deadline| now_micros > deadline)
+    }
+
+    /// Check that a team may win the match because the other team missed the report deadline:
+    /// the team reported the current round and the other team did not report it in time
+    pub fn check_timeout_claim(&self, team: Team, now_micros: u64) -> Result<(), HoverWarsError> {
+        if !self.accepts_round_reports() {
+            return Err(HoverWarsError::MatchNotInProgress);
+        }
+        if !self.report_deadline_passed(now_micros) {
+            return Err(HoverWarsError::DeadlineNotReached);
+        }
+        if !self.has_reported(team) || self.has_reported(team.opponent()) {
+            return Err(HoverWarsError::NoTimeoutToClaim);
+        }
+        Ok(())
+    }
+
+    /// Whether round reports are currently accepted
+    pub fn accepts_round_reports(&self) -> bool {
+        matches!(self.match_state, MatchState::InProgress | MatchState::Overtime | MatchState::RoundDisputed)
     }
 
-    pub fn apply_round_result(&mut self, result: RoundResult, timestamp: String) -> bool {
+    /// State of a match whose current round is being played
+    pub fn playing_state(&self) -> MatchState {
+        if self.overtime {
+            MatchState::Overtime
+        } else {
+            MatchState::InProgress
+        }
+    }
+
+    /// Whether a round is already recorded in the round history
+    pub fn has_round(&self, round_number: u32) -> bool {
+        self.round_history.iter().any(|round| round.round_number == round_number)
+    }
+
+    /// Whether a team has already reported the current round
+    pub fn has_reported(&self, team: Team) -> bool {
+        match team {
+            Team::Blue => self.blue_report.is_some(),
+            Team::Red => self.red_report.is_some(),
+        }
+    }
+
+    /// Record a team's proposed result and statistics for the current round.
+    /// Once both teams have reported, the reports are cleared and the round is either
+    /// confirmed (match back in play) or disputed (both teams must report again).
+    pub fn submit_round_report(&mut self, team: Team, result: RoundResult, stats: RoundStats) -> RoundConfirmation {
+        match team {
+            Team::Blue => {
+                self.blue_report = Some(result);
+                self.blue_stats = Some(stats);
+            }
+            Team::Red => {
+                self.red_report = Some(result);
+                self.red_stats = Some(stats);
+            }
+        }
+
+        match (self.blue_report, self.red_report) {
+            (Some(blue_result), Some(red_result)) => {
+                let blue_stats = self.blue_stats.take().unwrap_or_default();
+                let red_stats = self.red_stats.take().unwrap_or_default();
+                self.clear_round_reports();
+                if blue_result == red_result {
+                    self.match_state = self.playing_state();
+                    let stats = RoundStats::combine(blue_stats, red_stats, blue_result);
+                    RoundConfirmation::Confirmed { result: blue_result, stats }
+                } else {
+                    self.match_state = MatchState::RoundDisputed;
+                    RoundConfirmation::Disputed { blue_result, red_result }
+                }
+            }
+            _ => RoundConfirmation::Pending,
+        }
+    }
+
+    pub fn clear_round_reports(&mut self) {
+        self.report_deadline_micros = None;
+        self.blue_report = None;
+        self.red_report = None;
+        self.blue_stats = None;
+        self.red_stats = None;
+    }
+
+    pub fn apply_round_result(&mut self, result: RoundResult, stats: RoundStats, timestamp: String) -> bool {
+        self.clear_round_reports();
+        let overtime_round = self.overtime;
+
         // Apply score changes
         match result {
             RoundResult::BlueWin => {
-                self.blue_player.score += 1;
+                self.blue_score += 1;
             }
             RoundResult::RedWin => {
-                if let Some(ref mut red) = self.red_player {
-                    red.score += 1;
-                }
+                self.red_score += 1;
             }
             RoundResult::Draw => {
-                self.blue_player.score += 1;
-                if let Some(ref mut red) = self.red_player {
-                    red.score += 1;
+                if self.rules.draw_scoring == DrawScoring::BothScore {
+                    self.blue_score += 1;
+                    self.red_score += 1;
                 }
             }
         }
 
-        let red_score = self.red_player.as_ref().map(|p| p.score).unwrap_or(0);
+        let blue_score = self.blue_score;
+        let red_score = self.red_score;
 
         // Record round history
         self.round_history.push(RoundHistoryEntry {
             round_number: self.current_round,
             result,
-            blue_score_after: self.blue_player.score,
+            blue_score_after: blue_score,
             red_score_after: red_score,
             timestamp,
+            overtime: overtime_round,
+            stats,
         });
 
-        // Check for winner (first to 3 wins)
-        if self.blue_player.score >= 3 {
-            self.winner = Some(Team::Blue);
-            self.match_state = MatchState::Finished;
-            return true;
-        }
-        if red_score >= 3 {
-            self.winner = Some(Team::Red);
-            self.match_state = MatchState::Finished;
-            return true;
+        // Sudden death: the first decisive overtime round takes the match
+        if overtime_round {
+            match result {
+                RoundResult::BlueWin => return self.finish(Some(Team::Blue)),
+                RoundResult::RedWin => return self.finish(Some(Team::Red)),
+                RoundResult::Draw => {}
+            }
+        } else {
+            let target_reached = blue_score >= self.rules.rounds_to_win || red_score >= self.rules.rounds_to_win;
+            let limit_reached = self.rules.max_rounds.is_some_and(|max_rounds| self.current_round >= max_rounds);
+
+            // Regulation is over: the leader wins, and a level score (e.g. both teams reaching
+            // the target on a draw) is settled by the tie-break rule rather than by team order
+            if target_reached || limit_reached {
+                if blue_score != red_score {
+                    let leader = if blue_score > red_score { Team::Blue } else { Team::Red };
+                    return self.finish(Some(leader));
+                }
+                match self.rules.tie_break {
+                    TieBreak::Draw => return self.finish(None),
+                    TieBreak::SuddenDeath => {
+                        self.overtime = true;
+                        self.match_state = MatchState::Overtime;
+                    }
+                }
+            }
         }
 
         // Next round
@@ -242,11 +1572,127 @@ impl GameRoom {
         false
     }
 
+    /// End the match with the given winner (None for a draw)
+    fn finish(&mut self, winner: Option<Team>) -> bool {
+        self.winner = winner;
+        self.match_state = MatchState::Finished;
+        self.clear_round_reports();
+        true
+    }
+
     pub fn get_blue_score(&self) -> u32 {
-        self.blue_player.score
+        self.blue_score
     }
 
     pub fn get_red_score(&self) -> u32 {
-        self.red_player.as_ref().map(|p| p.score).unwrap_or(0)
+        self.red_score
+    }
+
+    /// Directory entry describing this room
+    pub fn to_listing(&self) -> LobbyListing {
+        LobbyListing {
+            room_id: self.room_id.clone(),
+            host_chain_id: self.host_chain_id.clone(),
+            host_name: self.host_name(),
+            match_state: self.match_state,
+            created_at: self.created_at.clone(),
+        }
+    }
+
+    /// Name of the event stream carrying this room's updates
+    pub fn stream_name(&self) -> String {
+        format!("{}{}", ROOM_STREAM_PREFIX, self.room_id)
+    }
+
+    /// Room ID encoded in a room stream name
+    pub fn room_id_from_stream_name(stream_name: &[u8]) -> Option<String> {
+        std::str::from_utf8(stream_name)
+            .ok()?
+            .strip_prefix(ROOM_STREAM_PREFIX)
+            .map(str::to_string)
+    }
+
+    /// Reconcile this copy of the room with an event published on the room's stream
+    pub fn apply_event(&mut self, event: &HoverWarsEvent) {
+        match event {
+            HoverWarsEvent::PlayerJoined { player, .. } => {
+                if self.player(&player.chain_id).is_none() {
+                    self.roster_mut(player.team).push(player.clone());
+                }
+            }
+            HoverWarsEvent::ReadyCheckStarted { deadline_micros, .. } => {
+                self.begin_ready_check(*deadline_micros);
+            }
+            HoverWarsEvent::PlayerReady { player_chain_id, ready, .. } => {
+                self.set_ready(player_chain_id, *ready);
+            }
+            HoverWarsEvent::MatchStarted { timestamp, .. } => {
+                self.start_match(timestamp.clone());
+            }
+            HoverWarsEvent::RoundCompleted { round_number, result, blue_score, red_score, timestamp, overtime, stats } => {
+                if self.has_round(*round_number) {
+                    return;
+                }
+
+                self.blue_score = *blue_score;
+                self.red_score = *red_score;
+                self.current_round = round_number + 1;
+                self.match_state = self.playing_state();
+                self.clear_round_reports();
+
+                self.round_history.push(RoundHistoryEntry {
+                    round_number: *round_number,
+                    result: *result,
+                    blue_score_after: *blue_score,
+                    red_score_after: *red_score,
+                    timestamp: timestamp.clone(),
+                    overtime: *overtime,
+                    stats: stats.clone(),
+                });
+            }
+            HoverWarsEvent::OvertimeStarted { .. } => {
+                self.overtime = true;
+                self.match_state = MatchState::Overtime;
+            }
+            HoverWarsEvent::RoundReported { deadline_micros, .. } => {
+                // Only the host keeps the reported results
+                self.report_deadline_micros = Some(*deadline_micros);
+            }
+            HoverWarsEvent::RoundDisputed { .. } => {
+                // Both players must report the round again
+                self.match_state = MatchState::RoundDisputed;
+                self.clear_round_reports();
+            }
+            HoverWarsEvent::MatchEnded { winner, blue_score, red_score, forfeit, .. } => {
+                self.blue_score = *blue_score;
+                self.red_score = *red_score;
+                self.forfeit = forfeit.clone();
+                // Finished matches keep the last played round as the current one
+                if let Some(last_round) = self.round_history.last() {
+                    self.current_round = last_round.round_number;
+                }
+                self.winner = *winner;
+                self.match_state = MatchState::Finished;
+                self.clear_round_reports();
+            }
+            HoverWarsEvent::PlayerLeft { player_chain_id, .. } => {
+                self.remove_player(player_chain_id);
+            }
+            HoverWarsEvent::PlayerKicked { player_chain_id, banned, .. } => {
+                self.kick_player(player_chain_id, *banned);
+            }
+            HoverWarsEvent::RematchRequested { requested_by, swap_sides, .. } => {
+                self.request_rematch(requested_by, *swap_sides);
+            }
+            HoverWarsEvent::RematchAccepted { player_chain_id, .. } => {
+                self.accept_rematch(player_chain_id);
+            }
+            HoverWarsEvent::RematchStarted { swap_sides, .. } => {
+                if *swap_sides {
+                    self.swap_sides();
+                }
+                self.rematch = None;
+            }
+        }
     }
 }
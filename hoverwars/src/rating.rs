@@ -0,0 +1,438 @@
+// Copyright (c) HoverWars
+// SPDX-License-Identifier: Apache-2.0
+
+//! Skill ratings (Elo and Glicko-2) computed with fixed-point arithmetic, so that every
+//! validator reaches the same result without relying on floating point.
+
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use serde::{Deserialize, Serialize};
+
+/// Number of fractional units in one [`Fixed`]
+const SCALE: i64 = 1_000_000_000;
+
+/// Rating every player starts from
+pub const INITIAL_RATING: i64 = 1500;
+/// Rating deviation of a new player (also the largest deviation a player can have)
+pub const INITIAL_DEVIATION: i64 = 350;
+/// Elo points at stake in a single match
+pub const ELO_K_FACTOR: i64 = 32;
+
+const LN_2: Fixed = Fixed(693_147_181);
+const LN_10: Fixed = Fixed(2_302_585_093);
+const PI_SQUARED: Fixed = Fixed(9_869_604_401);
+/// Ratio between the Glicko and Glicko-2 rating scales
+const GLICKO2_SCALE: Fixed = Fixed(173_717_800_000);
+/// Volatility of a new player
+const GLICKO2_INITIAL_VOLATILITY: Fixed = Fixed::from_ratio(6, 100);
+/// System constant constraining volatility changes
+const GLICKO2_TAU: Fixed = Fixed::from_ratio(1, 2);
+/// Convergence tolerance of the volatility iteration
+const GLICKO2_EPSILON: Fixed = Fixed(1_000);
+/// Upper bound on the volatility iteration steps
+const GLICKO2_MAX_ITERATIONS: u32 = 100;
+
+/// Signed fixed-point number with nine decimal places
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(SCALE);
+    pub const HALF: Fixed = Fixed(SCALE / 2);
+
+    pub const fn from_int(value: i64) -> Self {
+        Fixed(value * SCALE)
+    }
+
+    pub const fn from_ratio(numerator: i64, denominator: i64) -> Self {
+        Fixed(numerator * SCALE / denominator)
+    }
+
+    /// Nearest integer (halves round away from zero)
+    pub fn round(self) -> i64 {
+        if self.0 >= 0 {
+            (self.0 + SCALE / 2) / SCALE
+        } else {
+            (self.0 - SCALE / 2) / SCALE
+        }
+    }
+
+    pub fn abs(self) -> Self {
+        Fixed(self.0.abs())
+    }
+
+    pub fn clamp(self, min: Fixed, max: Fixed) -> Self {
+        Fixed(self.0.clamp(min.0, max.0))
+    }
+
+    fn from_wide(value: i128) -> Self {
+        Fixed(i64::try_from(value).expect("Fixed-point overflow"))
+    }
+
+    /// Square root (zero for negative values)
+    pub fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Fixed::ZERO;
+        }
+        let radicand = self.0 as u128 * SCALE as u128;
+
+        // Integer Newton iteration, starting above the root
+        let mut root = radicand;
+        let mut next = root.div_ceil(2);
+        while next < root {
+            root = next;
+            next = (root + radicand / root) / 2;
+        }
+        Fixed(root as i64)
+    }
+
+    /// Natural exponential (saturates for large arguments)
+    pub fn exp(self) -> Self {
+        if self < Fixed::from_int(-21) {
+            return Fixed::ZERO;
+        }
+        if self > Fixed::from_int(22) {
+            return Fixed(i64::MAX);
+        }
+
+        // e^x = 2^k * e^r with |r| <= ln(2) / 2
+        let k = (self / LN_2).round();
+        let remainder = self - LN_2 * Fixed::from_int(k);
+
+        let mut term = Fixed::ONE;
+        let mut sum = Fixed::ONE;
+        for n in 1..=30 {
+            term = term * remainder / Fixed::from_int(n);
+            if term == Fixed::ZERO {
+                break;
+            }
+            sum = sum + term;
+        }
+
+        if k >= 0 {
+            Fixed::from_wide((sum.0 as i128) << k)
+        } else {
+            Fixed(sum.0 >> -k)
+        }
+    }
+
+    /// Logistic function 1 / (1 + e^-x), computed without overflowing for large |x|
+    pub fn logistic(self) -> Self {
+        if self >= Fixed::ZERO {
+            Fixed::ONE / (Fixed::ONE + (-self).exp())
+        } else {
+            let exp = self.exp();
+            exp / (Fixed::ONE + exp)
+        }
+    }
+
+    /// Natural logarithm of a positive value
+    pub fn ln(self) -> Self {
+        assert!(self.0 > 0, "Logarithm of a non-positive number");
+
+        // x = m * 2^k with 1 <= m < 2
+        let mut mantissa = self.0 as i128;
+        let mut k = 0;
+        while mantissa >= 2 * SCALE as i128 {
+            mantissa /= 2;
+            k += 1;
+        }
+        while mantissa < SCALE as i128 {
+            mantissa *= 2;
+            k -= 1;
+        }
+        let mantissa = Fixed(mantissa as i64);
+
+        // ln(m) = 2 * atanh(z) with z = (m - 1) / (m + 1) <= 1/3
+        let z = (mantissa - Fixed::ONE) / (mantissa + Fixed::ONE);
+        let z_squared = z * z;
+        let mut power = z;
+        let mut sum = Fixed::ZERO;
+        for n in 0..30 {
+            let term = power / Fixed::from_int(2 * n + 1);
+            if term == Fixed::ZERO {
+                break;
+            }
+            sum = sum + term;
+            power = power * z_squared;
+        }
+
+        sum * Fixed::from_int(2) + LN_2 * Fixed::from_int(k)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0 + other.0)
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0 - other.0)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    fn neg(self) -> Fixed {
+        Fixed(-self.0)
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    fn mul(self, other: Fixed) -> Fixed {
+        Fixed::from_wide(self.0 as i128 * other.0 as i128 / SCALE as i128)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Fixed;
+
+    fn div(self, other: Fixed) -> Fixed {
+        Fixed::from_wide(self.0 as i128 * SCALE as i128 / other.0 as i128)
+    }
+}
+
+// Rating system enum
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RatingSystem {
+    /// Glicko-2: ratings carry a deviation and a volatility
+    #[default]
+    Glicko2,
+    /// Elo with a fixed K-factor (the deviation never changes)
+    Elo,
+}
+
+/// A player's skill estimate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkillRating {
+    pub rating: Fixed,
+    pub deviation: Fixed,
+    pub volatility: Fixed,
+}
+
+impl Default for SkillRating {
+    fn default() -> Self {
+        Self {
+            rating: Fixed::from_int(INITIAL_RATING),
+            deviation: Fixed::from_int(INITIAL_DEVIATION),
+            volatility: GLICKO2_INITIAL_VOLATILITY,
+        }
+    }
+}
+
+impl SkillRating {
+    /// Single opponent standing for a whole team: average rating and volatility,
+    /// root-mean-square deviation
+    pub fn composite(ratings: &[SkillRating]) -> SkillRating {
+        if ratings.is_empty() {
+            return SkillRating::default();
+        }
+        let count = Fixed::from_int(ratings.len() as i64);
+        let total = |value: fn(&SkillRating) -> Fixed| ratings.iter().map(value).fold(Fixed::ZERO, |sum, v| sum + v);
+
+        SkillRating {
+            rating: total(|r| r.rating) / count,
+            deviation: (total(|r| r.deviation * r.deviation) / count).sqrt(),
+            volatility: total(|r| r.volatility) / count,
+        }
+    }
+
+    /// Rating after one match against `opponent`, where `score` is 1 for a win,
+    /// 1/2 for a draw and 0 for a loss
+    pub fn update(self, system: RatingSystem, opponent: SkillRating, score: Fixed) -> SkillRating {
+        match system {
+            RatingSystem::Glicko2 => self.glicko2(opponent, score),
+            RatingSystem::Elo => self.elo(opponent, score),
+        }
+    }
+
+    fn elo(self, opponent: SkillRating, score: Fixed) -> SkillRating {
+        // E = 1 / (1 + 10^((R_opponent - R) / 400))
+        let expected = ((self.rating - opponent.rating) * LN_10 / Fixed::from_int(400)).logistic();
+
+        SkillRating {
+            rating: self.rating + Fixed::from_int(ELO_K_FACTOR) * (score - expected),
+            ..self
+        }
+    }
+
+    /// Glicko-2 update for a rating period made of a single match
+    fn glicko2(self, opponent: SkillRating, score: Fixed) -> SkillRating {
+        let mu = (self.rating - Fixed::from_int(INITIAL_RATING)) / GLICKO2_SCALE;
+        let phi = self.deviation / GLICKO2_SCALE;
+        let opponent_mu = (opponent.rating - Fixed::from_int(INITIAL_RATING)) / GLICKO2_SCALE;
+        let opponent_phi = opponent.deviation / GLICKO2_SCALE;
+
+        let g = Fixed::ONE / (Fixed::ONE + Fixed::from_int(3) * opponent_phi * opponent_phi / PI_SQUARED).sqrt();
+        let expected = (g * (mu - opponent_mu)).logistic();
+        // Near-certain outcomes are clamped for the variance only, so it stays within
+        // fixed-point range while expected wins still earn (almost) nothing
+        let bounded = expected.clamp(Fixed::from_ratio(1, 100), Fixed::from_ratio(99, 100));
+        let variance = Fixed::ONE / (g * g * bounded * (Fixed::ONE - bounded));
+        let improvement = variance * g * (score - expected);
+
+        let volatility = self.new_volatility(phi, variance, improvement);
+
+        let phi_star_squared = phi * phi + volatility * volatility;
+        let new_phi = Fixed::ONE / (Fixed::ONE / phi_star_squared + Fixed::ONE / variance).sqrt();
+        let new_mu = mu + new_phi * new_phi * g * (score - expected);
+
+        SkillRating {
+            rating: GLICKO2_SCALE * new_mu + Fixed::from_int(INITIAL_RATING),
+            deviation: (GLICKO2_SCALE * new_phi).clamp(Fixed::ZERO, Fixed::from_int(INITIAL_DEVIATION)),
+            volatility,
+        }
+    }
+
+    /// Volatility after the match (Illinois iteration from the Glicko-2 paper)
+    fn new_volatility(self, phi: Fixed, variance: Fixed, improvement: Fixed) -> Fixed {
+        let tau_squared = GLICKO2_TAU * GLICKO2_TAU;
+        let phi_squared = phi * phi;
+        let improvement_squared = improvement * improvement;
+        let a = (self.volatility * self.volatility).ln();
+
+        let f = |x: Fixed| {
+            let exp_x = x.exp();
+            let denominator = phi_squared + variance + exp_x;
+            exp_x * (improvement_squared - phi_squared - variance - exp_x) / (Fixed::from_int(2) * denominator * denominator)
+                - (x - a) / tau_squared
+        };
+
+        let mut lower = a;
+        let mut upper = if improvement_squared > phi_squared + variance {
+            (improvement_squared - phi_squared - variance).ln()
+        } else {
+            let mut k = 1;
+            while f(a - Fixed::from_int(k) * GLICKO2_TAU) < Fixed::ZERO && k < GLICKO2_MAX_ITERATIONS as i64 {
+                k += 1;
+            }
+            a - Fixed::from_int(k) * GLICKO2_TAU
+        };
+
+        let mut f_lower = f(lower);
+        let mut f_upper = f(upper);
+        for _ in 0..GLICKO2_MAX_ITERATIONS {
+            if (upper - lower).abs() <= GLICKO2_EPSILON || f_upper == f_lower {
+                break;
+            }
+            let next = lower + (lower - upper) * f_lower / (f_upper - f_lower);
+            let f_next = f(next);
+            if f_next.0.signum() * f_upper.0.signum() <= 0 {
+                lower = upper;
+                f_lower = f_upper;
+            } else {
+                f_lower = f_lower / Fixed::from_int(2);
+            }
+            upper = next;
+            f_upper = f_next;
+        }
+
+        (lower / Fixed::from_int(2)).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fixed-point value of a decimal literal, for comparisons
+    fn fixed(value: f64) -> Fixed {
+        Fixed((value * SCALE as f64).round() as i64)
+    }
+
+    fn assert_close(actual: Fixed, expected: f64, tolerance: f64) {
+        let difference = (actual - fixed(expected)).abs();
+        assert!(difference <= fixed(tolerance), "expected {} ± {}, got {:?}", expected, tolerance, actual);
+    }
+
+    #[test]
+    fn sqrt_matches_known_roots() {
+        assert_eq!(Fixed::from_int(4).sqrt(), Fixed::from_int(2));
+        assert_eq!(Fixed::ZERO.sqrt(), Fixed::ZERO);
+        assert_eq!(Fixed::from_int(-4).sqrt(), Fixed::ZERO);
+        assert_close(Fixed::from_int(2).sqrt(), std::f64::consts::SQRT_2, 1e-9);
+        assert_close(Fixed::from_ratio(1, 100).sqrt(), 0.1, 1e-9);
+    }
+
+    #[test]
+    fn exp_matches_known_values() {
+        assert_eq!(Fixed::ZERO.exp(), Fixed::ONE);
+        assert_close(Fixed::ONE.exp(), std::f64::consts::E, 1e-8);
+        assert_close((-Fixed::ONE).exp(), 1.0 / std::f64::consts::E, 1e-8);
+        assert_close(Fixed::from_int(10).exp(), 22026.465794806718, 1e-3);
+        assert_eq!(Fixed::from_int(-30).exp(), Fixed::ZERO);
+        assert_eq!(Fixed::from_int(30).exp(), Fixed(i64::MAX));
+    }
+
+    #[test]
+    fn ln_matches_known_values() {
+        assert_eq!(Fixed::ONE.ln(), Fixed::ZERO);
+        assert_close(fixed(std::f64::consts::E).ln(), 1.0, 1e-8);
+        assert_close(Fixed::from_int(10).ln(), std::f64::consts::LN_10, 1e-8);
+        assert_close(Fixed::from_ratio(36, 10_000).ln(), (0.0036f64).ln(), 1e-7);
+    }
+
+    #[test]
+    fn logistic_stays_in_range_for_large_arguments() {
+        assert_eq!(Fixed::ZERO.logistic(), Fixed::HALF);
+        assert_close(Fixed::ONE.logistic(), 0.7310585786, 1e-8);
+        assert_eq!(Fixed::from_int(1000).logistic(), Fixed::ONE);
+        assert_eq!(Fixed::from_int(-1000).logistic(), Fixed::ZERO);
+    }
+
+    #[test]
+    fn glicko2_reproduces_the_reference_example() {
+        // Glickman's example player (1500 / 200) beating the 1400 / 30 opponent
+        let player = SkillRating { deviation: Fixed::from_int(200), ..SkillRating::default() };
+        let opponent = SkillRating { rating: Fixed::from_int(1400), deviation: Fixed::from_int(30), ..SkillRating::default() };
+
+        let updated = player.update(RatingSystem::Glicko2, opponent, Fixed::ONE);
+        assert_close(updated.rating, 1563.6, 0.05);
+        assert_close(updated.deviation, 175.4, 0.05);
+        assert_close(updated.volatility, 0.06, 1e-4);
+    }
+
+    #[test]
+    fn elo_splits_the_k_factor_between_equal_players() {
+        let player = SkillRating::default();
+        let updated = player.update(RatingSystem::Elo, SkillRating::default(), Fixed::ONE);
+        assert_eq!(updated.rating, Fixed::from_int(INITIAL_RATING + ELO_K_FACTOR / 2));
+        assert_eq!(updated.deviation, player.deviation);
+    }
+
+    #[test]
+    fn lopsided_matches_do_not_overflow() {
+        let strong = SkillRating { rating: Fixed::from_int(10_000), ..SkillRating::default() };
+        let weak = SkillRating { rating: Fixed::from_int(800), ..SkillRating::default() };
+
+        for system in [RatingSystem::Glicko2, RatingSystem::Elo] {
+            for score in [Fixed::ZERO, Fixed::HALF, Fixed::ONE] {
+                strong.update(system, weak, score);
+                weak.update(system, strong, score);
+            }
+        }
+    }
+
+    #[test]
+    fn beating_a_much_weaker_player_stops_paying_off() {
+        let weak = SkillRating { rating: Fixed::from_int(800), ..SkillRating::default() };
+
+        for system in [RatingSystem::Glicko2, RatingSystem::Elo] {
+            let mut player = SkillRating::default();
+            for _ in 0..2000 {
+                player = player.update(system, weak, Fixed::ONE);
+            }
+            assert!(player.rating < Fixed::from_int(3000), "{:?} rating grew to {:?}", system, player.rating);
+        }
+    }
+}
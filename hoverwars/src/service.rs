@@ -5,18 +5,21 @@
 
 mod state;
 
-use std::sync::Arc;
+use std::{cmp::Ordering, sync::Arc};
 
 use async_graphql::{EmptySubscription, Object, Request, Response, Schema};
 use linera_sdk::{linera_base_types::WithServiceAbi, views::View, Service, ServiceRuntime};
-use hoverwars::{HoverWarsAbi, GameRoom, Player, Team, MatchState, RoundResult, RoundHistoryEntry};
+use hoverwars::{hash_invite_code, ArchivedMatch, HostHandover, HoverWarsAbi, LobbyAccess, LobbyVisibility, HoverWarsParameters, GameRoom, JoinAttempt, LobbyListing, MatchRules, OperationOutcome, MatchStats, Player, PlayerProfile, PlayerStanding, QueueEntry, QueueTicket, RejectedMessage, SECURITY_LOG_CAPACITY, Team, MatchState, RoundResult, RoundHistoryEntry, RoundStats};
 
 use self::state::HoverWarsState;
 
 linera_sdk::service!(HoverWarsService);
 
+/// Entries returned by a paginated query when no limit is given
+const DEFAULT_PAGE_SIZE: u32 = 20;
+
 pub struct HoverWarsService {
-    state: HoverWarsState,
+    state: Arc<HoverWarsState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -25,23 +28,24 @@ impl WithServiceAbi for HoverWarsService {
 }
 
 impl Service for HoverWarsService {
-    type Parameters = ();
+    type Parameters = HoverWarsParameters;
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
         let state = HoverWarsState::load(runtime.root_view_storage_context())
             .await
             .expect("Failed to load state");
         HoverWarsService {
-            state,
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
 
     async fn handle_query(&self, request: Request) -> Response {
-        let room = self.state.room.get().clone();
-        
         let schema = Schema::build(
-            QueryRoot { room: room.clone() },
+            QueryRoot {
+                state: self.state.clone(),
+                now_micros: self.runtime.system_time().micros(),
+            },
             MutationRoot { runtime: self.runtime.clone() },
             EmptySubscription,
         )
@@ -52,157 +56,413 @@ impl Service for HoverWarsService {
 }
 
 struct QueryRoot {
-    room: Option<GameRoom>,
+    state: Arc<HoverWarsState>,
+    now_micros: u64,
+}
+
+impl QueryRoot {
+    /// Load a room by ID
+    async fn load_room(&self, room_id: &str) -> Option<GameRoom> {
+        self.state.rooms.get(room_id).await.expect("Failed to load room")
+    }
 }
 
 #[Object]
 impl QueryRoot {
+    /// Get all rooms hosted on or joined from this chain
+    async fn rooms(&self) -> Vec<GameRoom> {
+        self.state.rooms.index_values().await
+            .expect("Failed to load rooms")
+            .into_iter()
+            .map(|(_, room)| room)
+            .collect()
+    }
+
+    /// Get the outcome of the latest operation of a kind executed on this chain
+    /// (mutations only schedule operations, so this is where their results and errors show up)
+    /// operation: operation name, e.g. "CreateLobby"
+    async fn last_operation(&self, operation: String) -> Option<OperationOutcome> {
+        self.state.operation_outcomes.get(&operation).await.expect("Failed to load operation outcome")
+    }
+
+    /// Get rooms waiting for an opponent from the lobby directory (directory chain only)
+    /// host_name: case-insensitive substring of the host's name
+    /// max_age_secs: only rooms created within this many seconds
+    async fn open_lobbies(&self, host_name: Option<String>, max_age_secs: Option<u64>) -> Vec<LobbyListing> {
+        let host_name = host_name.map(|name| name.to_lowercase());
+        self.state.lobby_directory.index_values().await
+            .expect("Failed to load lobby directory")
+            .into_iter()
+            .map(|(_, listing)| listing)
+            .filter(|listing| listing.match_state == MatchState::WaitingForOpponent)
+            .filter(|listing| {
+                host_name.as_ref().is_none_or(|name| listing.host_name.to_lowercase().contains(name))
+            })
+            .filter(|listing| {
+                max_age_secs.is_none_or(|max_age| {
+                    let created_at = listing.created_at.parse::<u64>().unwrap_or(0);
+                    self.now_micros.saturating_sub(created_at) <= max_age.saturating_mul(1_000_000)
+                })
+            })
+            .collect()
+    }
+
+    /// Get the status of a join request sent from this chain
+    async fn join_status(&self, room_id: String) -> Option<JoinAttempt> {
+        self.state.join_attempts.get(&room_id).await.expect("Failed to load join attempt")
+    }
+
+    /// Get the most recent cross-chain messages rejected because of an unexpected origin chain, newest first
+    /// before: only messages with a lower entry ID (the last entry ID of the previous page)
+    /// limit: maximum number of messages returned
+    async fn security_log(&self, before: Option<u32>, limit: Option<u32>) -> Vec<RejectedMessage> {
+        let count = *self.state.security_log_count.get();
+        let end = before.map_or(count, |before| count.min(before));
+        let start = end
+            .saturating_sub(limit.unwrap_or(DEFAULT_PAGE_SIZE))
+            .max(count.saturating_sub(SECURITY_LOG_CAPACITY));
+
+        let mut messages = Vec::new();
+        for entry_id in (start..end).rev() {
+            if let Some(message) = self.state.security_log.get(&entry_id).await.expect("Failed to load security log") {
+                messages.push(message);
+            }
+        }
+        messages
+    }
+
+    /// Get finished matches played on this chain, newest first
+    /// before: only matches with a lower match ID (the last match ID of the previous page)
+    /// limit: maximum number of matches returned
+    async fn match_history(&self, before: Option<u32>, limit: Option<u32>) -> Vec<ArchivedMatch> {
+        let count = self.state.match_archive.count();
+        let end = before.map_or(count, |before| count.min(before as usize));
+        let start = end.saturating_sub(limit.unwrap_or(DEFAULT_PAGE_SIZE) as usize);
+
+        let mut matches = self.state.match_archive.read(start..end).await.expect("Failed to load match archive");
+        matches.reverse();
+        matches
+    }
+
+    /// Get player standings, best first (leaderboard chain only)
+    /// first: maximum number of entries returned
+    /// after: chain ID of the last entry of the previous page
+    /// sort_by: ranking criterion (WINS by default)
+    async fn leaderboard(&self, first: Option<u32>, after: Option<String>, sort_by: Option<LeaderboardSort>) -> Vec<LeaderboardEntry> {
+        let sort_by = sort_by.unwrap_or_default();
+        let mut standings: Vec<PlayerStanding> = self.state.leaderboard.index_values().await
+            .expect("Failed to load leaderboard")
+            .into_iter()
+            .map(|(_, standing)| standing)
+            .collect();
+        // Ties keep a stable order so cursors stay valid between pages
+        standings.sort_by(|a, b| sort_by.compare(b, a).then_with(|| a.chain_id.cmp(&b.chain_id)));
+
+        let skip = after.map_or(0, |after| {
+            standings.iter().position(|standing| standing.chain_id == after).map_or(0, |position| position + 1)
+        });
+        standings.into_iter()
+            .enumerate()
+            .skip(skip)
+            .take(first.unwrap_or(DEFAULT_PAGE_SIZE) as usize)
+            .map(|(index, standing)| LeaderboardEntry {
+                rank: index as u32 + 1,
+                standing,
+            })
+            .collect()
+    }
+
+    /// Get the matchmaking ticket of the player using this chain
+    async fn queue_status(&self) -> Option<QueueTicket> {
+        self.state.queue_ticket.get().clone()
+    }
+
+    /// Get players waiting for an opponent (matchmaking chain only)
+    async fn matchmaking_queue(&self, mode: Option<String>) -> Vec<QueueEntry> {
+        self.state.matchmaking_queue.index_values().await
+            .expect("Failed to load matchmaking queue")
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .filter(|entry| mode.as_ref().is_none_or(|mode| &entry.mode == mode))
+            .collect()
+    }
+
+    /// Get the career statistics of the player using this chain
+    async fn profile(&self) -> PlayerProfile {
+        self.state.profile.get().clone()
+    }
+
+    /// Get an archived match by ID
+    #[graphql(name = "match")]
+    async fn archived_match(&self, id: u32) -> Option<ArchivedMatch> {
+        self.state.match_archive.get(id as usize).await.expect("Failed to load archived match")
+    }
+
     /// Get the full game room data
-    async fn room(&self) -> Option<&GameRoom> {
-        self.room.as_ref()
+    async fn room(&self, room_id: String) -> Option<GameRoom> {
+        self.load_room(&room_id).await
     }
     
     /// Get current match state
-    async fn match_state(&self) -> Option<MatchState> {
-        self.room.as_ref().map(|r| r.match_state)
+    async fn match_state(&self, room_id: String) -> Option<MatchState> {
+        self.load_room(&room_id).await.map(|r| r.match_state)
     }
     
-    /// Get both players
-    async fn players(&self) -> Vec<Player> {
-        self.room.as_ref().map_or(Vec::new(), |r| {
-            let mut players = vec![r.blue_player.clone()];
-            if let Some(ref red) = r.red_player {
-                players.push(red.clone());
-            }
-            players
-        })
+    /// Get all players, Blue first
+    async fn players(&self, room_id: String) -> Vec<Player> {
+        self.load_room(&room_id).await.map_or(Vec::new(), |r| r.players().cloned().collect())
     }
     
-    /// Get blue player info
-    async fn blue_player(&self) -> Option<Player> {
-        self.room.as_ref().map(|r| r.blue_player.clone())
+    /// Get blue team roster
+    async fn blue_team(&self, room_id: String) -> Vec<Player> {
+        self.load_room(&room_id).await.map_or(Vec::new(), |r| r.blue_team)
     }
     
-    /// Get red player info
-    async fn red_player(&self) -> Option<Player> {
-        self.room.as_ref().and_then(|r| r.red_player.clone())
+    /// Get red team roster
+    async fn red_team(&self, room_id: String) -> Vec<Player> {
+        self.load_room(&room_id).await.map_or(Vec::new(), |r| r.red_team)
     }
     
-    /// Get blue player score
-    async fn blue_score(&self) -> u32 {
-        self.room.as_ref().map(|r| r.blue_player.score).unwrap_or(0)
+    /// Get blue team score
+    async fn blue_score(&self, room_id: String) -> u32 {
+        self.load_room(&room_id).await.map(|r| r.blue_score).unwrap_or(0)
     }
     
-    /// Get red player score
-    async fn red_score(&self) -> u32 {
-        self.room.as_ref().and_then(|r| r.red_player.as_ref().map(|p| p.score)).unwrap_or(0)
+    /// Get red team score
+    async fn red_score(&self, room_id: String) -> u32 {
+        self.load_room(&room_id).await.map(|r| r.red_score).unwrap_or(0)
     }
     
-    /// Get blue player name
-    async fn blue_player_name(&self) -> Option<String> {
-        self.room.as_ref().map(|r| r.blue_player.name.clone())
-    }
-    
-    /// Get red player name
-    async fn red_player_name(&self) -> Option<String> {
-        self.room.as_ref().and_then(|r| r.red_player.as_ref().map(|p| p.name.clone()))
+    /// Get host name
+    async fn host_name(&self, room_id: String) -> Option<String> {
+        self.load_room(&room_id).await.map(|r| r.host_name())
     }
     
     /// Get current round number
-    async fn current_round(&self) -> u32 {
-        self.room.as_ref().map(|r| r.current_round).unwrap_or(0)
+    async fn current_round(&self, room_id: String) -> u32 {
+        self.load_room(&room_id).await.map(|r| r.current_round).unwrap_or(0)
     }
     
     /// Get round history
-    async fn round_history(&self) -> Vec<RoundHistoryEntry> {
-        self.room.as_ref().map_or(Vec::new(), |r| r.round_history.clone())
+    async fn round_history(&self, room_id: String) -> Vec<RoundHistoryEntry> {
+        self.load_room(&room_id).await.map_or(Vec::new(), |r| r.round_history)
     }
     
     /// Get winner team (None if match not finished)
-    async fn winner(&self) -> Option<Team> {
-        self.room.as_ref().and_then(|r| r.winner)
+    async fn winner(&self, room_id: String) -> Option<Team> {
+        self.load_room(&room_id).await.and_then(|r| r.winner)
     }
     
-    /// Get winner name (None if match not finished)
-    async fn winner_name(&self) -> Option<String> {
-        self.room.as_ref().and_then(|r| {
-            r.winner.map(|team| match team {
-                Team::Blue => r.blue_player.name.clone(),
-                Team::Red => r.red_player.as_ref().map(|p| p.name.clone()).unwrap_or_default(),
-            })
+    /// Get names of the winning team's players (empty if match not finished or drawn)
+    async fn winner_names(&self, room_id: String) -> Vec<String> {
+        self.load_room(&room_id).await.map_or(Vec::new(), |r| {
+            r.winner.map_or(Vec::new(), |team| r.roster(team).iter().map(|p| p.name.clone()).collect())
         })
     }
     
     /// Is match finished?
-    async fn is_match_finished(&self) -> bool {
-        self.room.as_ref().map(|r| r.match_state == MatchState::Finished).unwrap_or(false)
+    async fn is_match_finished(&self, room_id: String) -> bool {
+        self.load_room(&room_id).await.map(|r| r.match_state == MatchState::Finished).unwrap_or(false)
     }
     
-    /// Is waiting for opponent?
-    async fn is_waiting_for_opponent(&self) -> bool {
-        self.room.as_ref().map(|r| r.match_state == MatchState::WaitingForOpponent).unwrap_or(false)
+    /// Is waiting for players to fill the rosters?
+    async fn is_waiting_for_opponent(&self, room_id: String) -> bool {
+        self.load_room(&room_id).await.map(|r| r.match_state == MatchState::WaitingForOpponent).unwrap_or(false)
+    }
+    
+    /// Statistics of a room's current (or last) match, summed over its rounds
+    async fn match_stats(&self, room_id: String) -> Option<MatchStats> {
+        self.load_room(&room_id).await.map(|r| MatchStats::from_rounds(r.players(), &r.round_history))
     }
     
     /// Get comprehensive game status
-    async fn game_status(&self) -> Option<GameStatus> {
-        self.room.as_ref().map(|r| GameStatus {
+    async fn game_status(&self, room_id: String) -> Option<GameStatus> {
+        self.load_room(&room_id).await.map(|r| GameStatus {
+            room_id: r.room_id.clone(),
             match_state: r.match_state,
-            blue_player_name: r.blue_player.name.clone(),
-            red_player_name: r.red_player.as_ref().map(|p| p.name.clone()),
-            blue_score: r.blue_player.score,
-            red_score: r.red_player.as_ref().map(|p| p.score).unwrap_or(0),
+            ready_deadline_micros: r.ready_deadline_micros,
+            report_deadline_micros: r.report_deadline_micros,
+            blue_team: r.blue_team.iter().map(|p| p.name.clone()).collect(),
+            red_team: r.red_team.iter().map(|p| p.name.clone()).collect(),
+            blue_score: r.blue_score,
+            red_score: r.red_score,
             current_round: r.current_round,
             winner: r.winner,
             rounds_played: r.round_history.len() as u32,
+            rules: r.rules,
         })
     }
 }
 
 #[derive(async_graphql::SimpleObject)]
 struct GameStatus {
+    room_id: String,
     match_state: MatchState,
-    blue_player_name: String,
-    red_player_name: Option<String>,
+    ready_deadline_micros: Option<u64>,
+    /// Deadline for the other team's report once one team reported the current round (microseconds)
+    report_deadline_micros: Option<u64>,
+    blue_team: Vec<String>,
+    red_team: Vec<String>,
     blue_score: u32,
     red_score: u32,
     current_round: u32,
     winner: Option<Team>,
     rounds_played: u32,
+    rules: MatchRules,
+}
+
+#[derive(async_graphql::Enum, Clone, Copy, Default, PartialEq, Eq)]
+enum LeaderboardSort {
+    #[default]
+    Wins,
+    WinRate,
+    MatchesPlayed,
+    RoundsWon,
+    Rating,
 }
 
+impl LeaderboardSort {
+    fn compare(self, a: &PlayerStanding, b: &PlayerStanding) -> Ordering {
+        match self {
+            LeaderboardSort::Wins => a.matches_won.cmp(&b.matches_won),
+            LeaderboardSort::WinRate => {
+                let a_rate = a.matches_won as u64 * b.matches_played as u64;
+                let b_rate = b.matches_won as u64 * a.matches_played as u64;
+                a_rate.cmp(&b_rate)
+            }
+            LeaderboardSort::MatchesPlayed => a.matches_played.cmp(&b.matches_played),
+            LeaderboardSort::RoundsWon => a.rounds_won.cmp(&b.rounds_won),
+            LeaderboardSort::Rating => a.skill.rating.cmp(&b.skill.rating),
+        }
+    }
+}
+
+#[derive(async_graphql::SimpleObject)]
+struct LeaderboardEntry {
+    rank: u32,
+    standing: PlayerStanding,
+}
+
+/// Mutations schedule an operation and describe what was scheduled; the operation's result
+/// (or error) is read back with lastOperation
 struct MutationRoot {
     runtime: Arc<ServiceRuntime<HoverWarsService>>,
 }
 
 #[Object]
 impl MutationRoot {
-    /// Create a new game lobby (caller becomes Blue Team host)
-    async fn create_lobby(&self, host_name: String) -> String {
+    /// Create a new game lobby (caller becomes Blue Team host); lastOperation returns its room ID
+    /// rules: overrides the application's default match rules
+    /// visibility: PRIVATE lobbies are unlisted and only admit invited chains
+    /// invite_code: code admitting players to a private lobby (only its hash is stored)
+    /// allowed_chain_ids: chains admitted to a private lobby without a code
+    async fn create_lobby(
+        &self,
+        host_name: String,
+        rules: Option<MatchRules>,
+        visibility: Option<LobbyVisibility>,
+        invite_code: Option<String>,
+        allowed_chain_ids: Option<Vec<String>>,
+    ) -> String {
+        let access = LobbyAccess {
+            visibility: visibility.unwrap_or_default(),
+            invite_code_hash: invite_code.as_deref().map(hash_invite_code),
+            allowed_chain_ids: allowed_chain_ids.unwrap_or_default(),
+        };
         self.runtime.schedule_operation(&hoverwars::Operation::CreateLobby { 
-            host_name: host_name.clone() 
+            host_name: host_name.clone(),
+            rules,
+            access,
         });
-        format!("Lobby created by host '{}' as Blue Team", host_name)
+        format!("Lobby creation scheduled for host '{}'", host_name)
     }
     
-    /// Join an existing lobby (caller becomes Red Team)
-    async fn join_lobby(&self, host_chain_id: String, player_name: String) -> String {
+    /// Join an existing lobby (caller is placed on the team with an open slot)
+    /// invite_code: required by private lobbies that didn't allow the caller's chain
+    async fn join_lobby(&self, host_chain_id: String, room_id: String, player_name: String, invite_code: Option<String>) -> String {
         self.runtime.schedule_operation(&hoverwars::Operation::JoinLobby { 
             host_chain_id: host_chain_id.clone(), 
-            player_name: player_name.clone() 
+            room_id: room_id.clone(),
+            player_name: player_name.clone(),
+            invite_code,
         });
-        format!("Join request sent to host '{}' by player '{}' as Red Team", host_chain_id, player_name)
+        format!("Join request for room '{}' on host '{}' scheduled for player '{}'", room_id, host_chain_id, player_name)
     }
     
-    /// Report round result (host only)
+    /// Report round result (applied once both players report the same result)
+    /// round_number: round the result is for; stale or repeated reports are rejected
     /// result: BLUE_WIN, RED_WIN, or DRAW
-    async fn report_round_result(&self, result: RoundResult) -> String {
-        self.runtime.schedule_operation(&hoverwars::Operation::ReportRoundResult { result });
-        format!("Round result reported: {:?}", result)
+    /// stats: the round's statistics for every player of the reporting team (only the team's first report is kept)
+    async fn report_round_result(&self, room_id: String, round_number: u32, result: RoundResult, stats: RoundStats) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::ReportRoundResult { room_id, round_number, result, stats });
+        format!("Round {} report of {:?} scheduled", round_number, result)
+    }
+    
+    /// Confirm (or withdraw) readiness during a room's ready check
+    async fn set_ready(&self, room_id: String, ready: bool) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::SetReady { room_id: room_id.clone(), ready });
+        format!("Readiness {} for room '{}' scheduled", ready, room_id)
+    }
+    
+    /// Set the display name of this chain's player profile
+    async fn set_profile(&self, display_name: String) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::SetProfile { display_name: display_name.clone() });
+        format!("Profile display name change to '{}' scheduled", display_name)
+    }
+    
+    /// Ask the matchmaking chain for an opponent with a similar rating
+    /// mode: players are only paired within the same mode
+    async fn enter_queue(&self, mode: String, rating: u32, player_name: String) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::EnterQueue { mode: mode.clone(), rating, player_name });
+        format!("Entry into '{}' matchmaking queue at rating {} scheduled", mode, rating)
+    }
+    
+    /// Leave the matchmaking queue
+    async fn leave_queue(&self) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::LeaveQueue);
+        "Leaving the matchmaking queue scheduled".to_string()
+    }
+    
+    /// Remove a guest from a hosted room (host only, not while a match is in progress)
+    /// ban: also stop the guest's chain from joining again
+    async fn kick_player(&self, room_id: String, chain_id: String, ban: bool) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::KickPlayer { room_id: room_id.clone(), chain_id: chain_id.clone(), ban });
+        format!("Kick of {} from room '{}' scheduled", chain_id, room_id)
     }
     
-    /// Leave the current lobby
-    async fn leave_lobby(&self) -> String {
-        self.runtime.schedule_operation(&hoverwars::Operation::LeaveLobby);
-        "Leave lobby request scheduled".to_string()
+    /// Give up the match in play; the other team wins
+    /// reason: recorded with the result
+    async fn concede(&self, room_id: String, reason: Option<String>) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::Concede { room_id: room_id.clone(), reason });
+        format!("Concession of room '{}' scheduled", room_id)
+    }
+    
+    /// Win the match after the other team stayed silent past the report deadline
+    async fn claim_timeout_win(&self, room_id: String) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::ClaimTimeoutWin { room_id: room_id.clone() });
+        format!("Timeout claim for room '{}' scheduled", room_id)
+    }
+    
+    /// Propose a rematch with the same players once the match is finished
+    /// swap_sides: Blue and Red switch rosters for the rematch
+    async fn request_rematch(&self, room_id: String, swap_sides: bool) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::RequestRematch { room_id: room_id.clone(), swap_sides });
+        format!("Rematch request for room '{}' scheduled", room_id)
+    }
+    
+    /// Accept the pending rematch (its ready check starts once every player accepted)
+    async fn accept_rematch(&self, room_id: String) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::AcceptRematch { room_id: room_id.clone() });
+        format!("Rematch acceptance for room '{}' scheduled", room_id)
+    }
+    
+    /// Leave a lobby
+    /// handover: what happens to the room when the host leaves (CLOSE_ROOM by default)
+    async fn leave_lobby(&self, room_id: String, handover: Option<HostHandover>) -> String {
+        self.runtime.schedule_operation(&hoverwars::Operation::LeaveLobby {
+            room_id: room_id.clone(),
+            handover: handover.unwrap_or_default(),
+        });
+        format!("Leave request scheduled for room '{}'", room_id)
     }
 }
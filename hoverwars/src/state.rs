@@ -1,12 +1,43 @@
-use linera_sdk::views::{linera_views, RegisterView, RootView, ViewStorageContext};
-use hoverwars::GameRoom;
+use linera_sdk::views::{linera_views, LogView, MapView, RegisterView, RootView, SetView, ViewStorageContext};
+use hoverwars::{ArchivedMatch, GameRoom, JoinAttempt, LobbyListing, OperationOutcome, PlayerProfile, PlayerStanding, QueueEntry, QueueTicket, RejectedMessage};
 
 /// The application state for HoverWars Game
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct HoverWarsState {
-    /// Game room data
-    pub room: RegisterView<Option<GameRoom>>,
-    /// Host chain ID that player is subscribed to (to prevent duplicate subscriptions)
-    pub subscribed_to_host: RegisterView<Option<String>>,
+    /// Game rooms hosted on or joined from this chain, keyed by room ID
+    pub rooms: MapView<String, GameRoom>,
+    /// Number of rooms created on this chain (used to derive unique room IDs)
+    pub rooms_created: RegisterView<u64>,
+    /// Outcome of the latest operation of each kind executed on this chain, keyed by operation name
+    pub operation_outcomes: MapView<String, OperationOutcome>,
+    /// Number of operations executed on this chain
+    pub operations_executed: RegisterView<u64>,
+    /// Join requests sent from this chain, keyed by room ID
+    pub join_attempts: MapView<String, JoinAttempt>,
+    /// Host chain ID each joined room is subscribed to, keyed by room ID (to prevent duplicate subscriptions)
+    pub subscribed_to_host: MapView<String, String>,
+    /// Room version at which a full state sync was requested, keyed by room ID (until the host replies)
+    pub pending_state_syncs: MapView<String, u64>,
+    /// Public lobby directory, keyed by room ID (only populated on the directory chain)
+    pub lobby_directory: MapView<String, LobbyListing>,
+    /// Most recent cross-chain messages rejected because they came from an unexpected chain,
+    /// keyed by entry ID (only the last SECURITY_LOG_CAPACITY entries are kept)
+    pub security_log: MapView<u32, RejectedMessage>,
+    /// Number of cross-chain messages ever rejected on this chain (the next entry ID)
+    pub security_log_count: RegisterView<u32>,
+    /// Finished matches played on this chain, oldest first (indexed by match ID)
+    pub match_archive: LogView<ArchivedMatch>,
+    /// Career statistics of the player using this chain
+    pub profile: RegisterView<PlayerProfile>,
+    /// Player standings keyed by chain ID (only populated on the leaderboard chain)
+    pub leaderboard: MapView<String, PlayerStanding>,
+    /// Result keys of the matches already counted in the leaderboard (leaderboard chain only)
+    pub counted_matches: SetView<String>,
+    /// Matchmaking ticket of the player using this chain
+    pub queue_ticket: RegisterView<Option<QueueTicket>>,
+    /// Players waiting for an opponent, keyed by chain ID (only populated on the matchmaking chain)
+    pub matchmaking_queue: MapView<String, QueueEntry>,
+    /// Paired opponents waiting for their host's lobby, keyed by host chain ID (matchmaking chain only)
+    pub pending_matches: MapView<String, QueueEntry>,
 }
@@ -176,6 +176,8 @@ io.on('connection', (socket) => {
                 kills: 0,
                 deaths: 0,
                 goals: 0,
+                damageDealt: 0,
+                oilPickups: 0,
                 isReady: true,
                 isDead: false,
                 respawnTime: 0,
@@ -233,6 +235,9 @@ io.on('connection', (socket) => {
         if (!room || !room.players[targetId] || room.players[targetId].isDead) return;
 
         room.players[targetId].health -= damage;
+        if (room.players[socket.id]) {
+            room.players[socket.id].damageDealt += damage;
+        }
 
         io.to(targetId).emit('takeDamage', damage);
         socket.emit('hitConfirm');
@@ -276,6 +281,7 @@ io.on('connection', (socket) => {
         room.matchState.isCarryingOil = true;
         room.matchState.oilHolder = room.players[socket.id].team;
         room.matchState.carrierId = socket.id;
+        room.players[socket.id].oilPickups += 1;
 
         emitToRoom(room.hostChainId, 'gameTick', { time: room.matchTime, matchState: room.matchState, players: room.players });
         emitToRoom(room.hostChainId, 'systemLog', { msg: `🛢️ OIL SECURED BY ${room.players[socket.id].team} TEAM!`, type: 'warning' });
@@ -81,10 +81,20 @@ const WaitingScreen = ({ team }: { team: string }) => {
   );
 };
 
+// Mounts inside the scene's Suspense boundary, i.e. once the map's assets have loaded
+const SceneLoaded = ({ onLoaded }: { onLoaded: () => void }) => {
+  useEffect(() => {
+    onLoaded();
+  }, [onLoaded]);
+  return null;
+};
+
 const App: React.FC = () => {
   const { client, application, ready: lineraReady, chainId: myChainId } = useLinera();
   const [gameState, setGameState] = useState<GameState | 'spectating' | 'game_over' | 'countdown' | 'lobby' | 'waiting'>('lobby');
   const [playerName, setPlayerName] = useState<string>('PILOT');
+  const [roomId, setRoomId] = useState<string>('');
+  const [sceneLoaded, setSceneLoaded] = useState(false);
 
   const [activeIslandId, setActiveIslandId] = useState<string | null>(null);
   const [health, setHealth] = useState(100);
@@ -113,6 +123,12 @@ const App: React.FC = () => {
   const [isHost, setIsHost] = useState<boolean>(false);
   const playerPosRef = useRef(new THREE.Vector3(0, 0, 0));
   const playerRotRef = useRef(0);
+  // Running totals of each boat's stats when the current round began, keyed by chain ID
+  // (the server only keeps match totals)
+  const roundStartRef = useRef(Date.now());
+  // Round being played, as numbered on-chain (every round ends with an oil delivery)
+  const roundNumberRef = useRef(1);
+  const statsBaselineRef = useRef<Record<string, { kills: number, deaths: number, damageDealt: number, oilPickups: number, goals: number }>>({});
 
   const [otherPlayers, setOtherPlayers] = useState<any>({});
   const [allPlayersList, setAllPlayersList] = useState<any[]>([]);
@@ -224,6 +240,30 @@ const App: React.FC = () => {
       console.log("♻️ ROUND RESET! SYNCING WITH BLOCKCHAIN...");
       playGlobalSound('/sounds/siren.mp3', 0.6);
 
+      // This round's stats for every boat on my team, reported with the result
+      // (the host keeps the first report of each team, so each report covers the whole team)
+      const teamStats = Object.values(players)
+        .filter((p: any) => p.team === myTeam && p.chainId)
+        .map((p: any) => {
+          const totals = {
+            kills: p.kills ?? 0,
+            deaths: p.deaths ?? 0,
+            damageDealt: p.damageDealt ?? 0,
+            oilPickups: p.oilPickups ?? 0,
+            goals: p.goals ?? 0
+          };
+          const baseline = statsBaselineRef.current[p.chainId] ?? { kills: 0, deaths: 0, damageDealt: 0, oilPickups: 0, goals: 0 };
+          statsBaselineRef.current[p.chainId] = totals;
+          return `{ chainId: ${JSON.stringify(p.chainId)}, `
+            + `kills: ${totals.kills - baseline.kills}, `
+            + `deaths: ${totals.deaths - baseline.deaths}, `
+            + `damageDealt: ${Math.round(totals.damageDealt - baseline.damageDealt)}, `
+            + `oilPickups: ${totals.oilPickups - baseline.oilPickups}, `
+            + `oilDeliveries: ${totals.goals - baseline.goals} }`;
+        });
+      const durationSecs = Math.max(1, Math.round((Date.now() - roundStartRef.current) / 1000));
+      const stats = `{ durationSecs: ${durationSecs}, endReason: OIL_DELIVERED, players: [${teamStats.join(', ')}] }`;
+
       if (socket.id && players[socket.id]) {
         const myData = players[socket.id];
         playerPosRef.current.set(myData.position.x, myData.position.y, myData.position.z);
@@ -240,31 +280,40 @@ const App: React.FC = () => {
       setIsReloading(false);
       setLastScorer(scorerTeam);
 
+      // Reports name the round they are for, so a repeated report cannot count twice
+      const roundNumber = roundNumberRef.current;
+      roundNumberRef.current += 1;
+
       // Show SYNCING state (not countdown yet - wait for both to confirm)
       setGameState('syncing');
       setCountdownValue(0);
 
-      // 🔗 SCORER: Call Linera mutation -> Emit confirmation on success
-      // If I am the team that scored (or I am host acting on behalf? No, contract allows dynamic)
-      // Actually, if blue scored, blue submits. If red scored, red submits.
-      if (scorerTeam === myTeam && application && lineraReady) {
+      // 🔗 EVERY PLAYER: Call Linera mutation -> Emit confirmation once done
+      // A round only counts once both teams reported the same result
+      if (application && lineraReady) {
         try {
           const result = scorerTeam === 'BLUE' ? 'BLUE_WIN' : 'RED_WIN';
-          console.log(`[Linera] I scored! Reporting round result: ${result}`);
+          console.log(`[Linera] Reporting round ${roundNumber} result: ${result}`);
 
           await application.query(JSON.stringify({
-            query: `mutation { reportRoundResult(result: ${result}) }`
+            query: `mutation { reportRoundResult(roomId: ${JSON.stringify(roomId)}, roundNumber: ${roundNumber}, result: ${result}, stats: ${stats}) }`
+          }));
+
+          // Mutations only schedule operations; the report's result is read back from the chain
+          const res = await application.query(JSON.stringify({
+            query: `query { lastOperation(operation: "ReportRoundResult") { error } }`
           }));
+          const json = typeof res === "string" ? JSON.parse(res) : res;
+          const outcome = json?.data?.lastOperation;
+          if (outcome?.error) throw new Error(outcome.error);
 
           console.log('[Linera] Round result reported successfully');
-          socket.emit('roundConfirm'); // Submitter confirms immediately after success
+          socket.emit('roundConfirm');
         } catch (e) {
           console.error('[Linera] Failed to report round result:', e);
           socket.emit('roundConfirm');
         }
       }
-      // 🔗 OPPONENT (didn't score this round): Waits for onNotification in separate useEffect.
-      // 🔗 GUEST: Does NOT confirm here. Waits for onNotification in separate useEffect.
     });
 
     // 🎮 Both players confirmed - NOW show scorer and start countdown
@@ -299,6 +348,8 @@ const App: React.FC = () => {
     });
 
     socket.on('gameStart', () => {
+      roundStartRef.current = Date.now();
+      roundNumberRef.current = 1;
       setGameState('playing');
       setMatchTime(900);
       addSystemLog("⚠️ BATTLE STATION READY! ENGAGE!", "success");
@@ -368,57 +419,50 @@ const App: React.FC = () => {
       socket.off('killConfirm');
       socket.off('enemyShoot');
     };
-  }, [addSystemLog, gameState, otherPlayers, playGlobalSound, myTeam, isHost, application, lineraReady]);
+  }, [addSystemLog, gameState, otherPlayers, playGlobalSound, myTeam, isHost, roomId, application, lineraReady]);
 
-  // 🔗 Separate effect for Linera notifications (Guest Sync)
+  // 🔗 Confirm ready checks only once the map has loaded (checked again on every chain notification)
   useEffect(() => {
-    if (!client || !lineraReady || !application) return;
-
-    const handleNotification = async (evt: any) => {
-      // If we are syncing and receive a notification, it means the round result block is processed
-      // SCORER confirms after mutation, OPPONENT (Non-Scorer) confirms here
-      console.log(`[Linera] 📩 Notification received! GameState: ${gameState}, IsHost: ${isHost}, Scorer: ${lastScorer}, MyTeam: ${myTeam}`);
-
-      if (gameState === 'syncing' && lastScorer !== myTeam) {
-        console.log('[Linera] I am the OPPONENT. Verifying state from blockchain...');
-
-        try {
-          // Verify with a query that the round actually updated
-          const res = await application.query(JSON.stringify({
-            query: `query { gameStatus { currentRound blueScore redScore } }`
+    if (!sceneLoaded || !roomId || !client || !application || !lineraReady) return;
+
+    const readyUp = async () => {
+      try {
+        const res = await application.query(JSON.stringify({
+          query: `query { room(roomId: ${JSON.stringify(roomId)}) { matchState blueTeam { chainId ready } redTeam { chainId ready } } }`
+        }));
+        const json = typeof res === "string" ? JSON.parse(res) : res;
+        const room = json?.data?.room;
+        if (room?.matchState !== 'READY_CHECK') return;
+
+        const me = [...room.blueTeam, ...room.redTeam].find((p: any) => p.chainId === myChainId);
+        if (me && !me.ready) {
+          console.log('[Linera] Map loaded, confirming the ready check');
+          await application.query(JSON.stringify({
+            query: `mutation { setReady(roomId: ${JSON.stringify(roomId)}, ready: true) }`
           }));
-
-          console.log('[Linera] 🔍 Query response raw:', res);
-          const json = typeof res === "string" ? JSON.parse(res) : res;
-          const data = json?.data?.gameStatus;
-
-          if (data) {
-            console.log(`[Linera] ✅ Verified state: Round ${data.currentRound}, Blue: ${data.blueScore}, Red: ${data.redScore}`);
-            socket.emit('roundConfirm');
-          } else {
-            console.warn('[Linera] ⚠️ verification failed: no data in response', json);
-          }
-        } catch (e) {
-          console.error('[Linera] ❌ Verification query failed:', e);
         }
-      } else {
-        console.log('[Linera] Ignoring notification (either not syncing or I am the scorer who already confirmed)');
+      } catch (e) {
+        console.error('[Linera] Ready check failed:', e);
       }
     };
 
-    const maybeUnsubscribe = (client as any).onNotification?.(handleNotification);
+    const maybeUnsubscribe = (client as any).onNotification?.(readyUp);
+    readyUp();
 
     return () => {
       if (typeof maybeUnsubscribe === 'function') {
         try { maybeUnsubscribe(); } catch { }
       } else {
-        try { (client as any).offNotification?.(handleNotification); } catch { }
+        try { (client as any).offNotification?.(readyUp); } catch { }
       }
     };
-  }, [client, lineraReady, gameState, isHost, application, lastScorer, myTeam]);
+  }, [sceneLoaded, roomId, client, application, lineraReady, myChainId]);
+
+  const handleSceneLoaded = useCallback(() => setSceneLoaded(true), []);
 
-  const handleJoinGame = (name: string, hostChainId: string, playerChainId: string, isHostParam: boolean) => {
+  const handleJoinGame = (name: string, joinedRoomId: string, hostChainId: string, playerChainId: string, isHostParam: boolean) => {
     setPlayerName(name);
+    setRoomId(joinedRoomId);
     setIsHost(isHostParam);
     // Send chainId to server for synchronization
     socket.emit('joinGame', { name: name, chainId: playerChainId, hostChainId: hostChainId, isHost: isHostParam });
@@ -448,6 +492,7 @@ const App: React.FC = () => {
       } else {
         setGameState('playing');
         setLastScorer(null);
+        roundStartRef.current = Date.now();
         addSystemLog("🟢 ROUND STARTED!", "success");
       }
     }
@@ -533,6 +578,7 @@ const App: React.FC = () => {
             <fog attach="fog" args={['#acc1d1', 100, 2500]} />
             <AdaptiveDpr pixelated />
             <Suspense fallback={null}>
+              <SceneLoaded onLoaded={handleSceneLoaded} />
 
               <LocalEngineSound playerPosRef={playerPosRef} gameState={gameState} />
 
@@ -602,7 +648,7 @@ const App: React.FC = () => {
           )}
 
           {/* Linera blockchain scores overlay */}
-          {(gameState === 'playing' || gameState === 'countdown' || gameState === 'syncing') && <LineraScoreDisplay />}
+          {(gameState === 'playing' || gameState === 'countdown' || gameState === 'syncing') && <LineraScoreDisplay roomId={roomId} />}
 
           {/* Syncing with blockchain overlay */}
           {gameState === 'syncing' && (
@@ -634,7 +680,7 @@ const App: React.FC = () => {
                   try {
                     console.log('[Linera] Leaving lobby...');
                     await application.query(JSON.stringify({
-                      query: `mutation { leaveLobby }`
+                      query: `mutation { leaveLobby(roomId: ${JSON.stringify(roomId)}) }`
                     }));
                   } catch (e) {
                     console.error('[Linera] Failed to leave lobby:', e);
@@ -2,7 +2,7 @@ import React, { useState, useEffect, useCallback } from 'react';
 import { useLinera } from './LineraProvider';
 
 interface LobbyProps {
-    onJoin: (name: string, hostChainId: string, playerChainId: string, isHost: boolean) => void;
+    onJoin: (name: string, roomId: string, hostChainId: string, playerChainId: string, isHost: boolean) => void;
 }
 
 const LineraLogo = () => (
@@ -17,9 +17,16 @@ const LineraLogo = () => (
 
 type LobbyScreen = 'menu' | 'creating' | 'joining' | 'waiting';
 
+const escapeGqlString = (value: string) =>
+    value.replace(/\\/g, "\\\\").replace(/"/g, '\\"').replace(/\r/g, "\\r").replace(/\n/g, "\\n");
+
+// Room IDs are "<host chain ID>-<sequence>"
+const hostChainIdOf = (roomId: string) => roomId.slice(0, roomId.lastIndexOf('-'));
+
 export const GameLobby: React.FC<LobbyProps> = ({ onJoin }) => {
     const [name, setName] = useState('');
-    const [hostChainId, setHostChainId] = useState('');
+    const [roomId, setRoomId] = useState('');
+    const [isHost, setIsHost] = useState(false);
     const [screen, setScreen] = useState<LobbyScreen>('menu');
     const [isProcessing, setIsProcessing] = useState(false);
     const [statusText, setStatusText] = useState('');
@@ -44,20 +51,21 @@ export const GameLobby: React.FC<LobbyProps> = ({ onJoin }) => {
         return () => clearInterval(interval);
     }, []);
 
-    // Poll for opponent when waiting in lobby
+    // Poll the room while waiting in lobby until the rosters are full
     useEffect(() => {
-        if (screen !== 'waiting' || !application || !ready) return;
+        if (screen !== 'waiting' || !roomId || !application || !ready) return;
 
         const pollInterval = setInterval(async () => {
             try {
-                const res = await application.query('{ "query": "query { room { matchState bluePlayer { name chainId } redPlayer { name chainId } } }" }');
+                const res = await application.query(JSON.stringify({
+                    query: `query { room(roomId: "${escapeGqlString(roomId)}") { matchState } }`
+                }));
                 const json = typeof res === "string" ? JSON.parse(res) : res;
                 const room = json?.data?.room;
 
-                if (room && room.matchState === 'IN_PROGRESS') {
-                    // Opponent joined, transition to game
-                    const isHost = room.bluePlayer?.chainId === chainId;
-                    onJoin(name, chainId || '', chainId || '', isHost);
+                if (room && room.matchState !== 'WAITING_FOR_OPPONENT') {
+                    // Rosters are full, load the map (the game readies up once it has loaded)
+                    onJoin(name, roomId, hostChainIdOf(roomId), chainId || '', isHost);
                 }
             } catch (e) {
                 console.error('[Linera Poll] Error:', e);
@@ -65,19 +73,40 @@ export const GameLobby: React.FC<LobbyProps> = ({ onJoin }) => {
         }, 2000);
 
         return () => clearInterval(pollInterval);
-    }, [screen, application, ready, chainId, name, onJoin]);
-
-    const escapeGqlString = (value: string) =>
-        value.replace(/\\/g, "\\\\").replace(/"/g, '\\"').replace(/\r/g, "\\r").replace(/\n/g, "\\n");
+    }, [screen, roomId, isHost, application, ready, chainId, name, onJoin]);
+
+    // Mutations only schedule operations; their results are read back from the chain
+    const lastOperation = async (operation: string) => {
+        if (!application) return null;
+        const res = await application.query(JSON.stringify({
+            query: `query { lastOperation(operation: "${operation}") { sequence error response { roomId } } }`
+        }));
+        const json = typeof res === "string" ? JSON.parse(res) : res;
+        return json?.data?.lastOperation ?? null;
+    };
 
     const handleCreateLobby = async () => {
         if (!name.trim() || !application || !ready) return;
         setIsProcessing(true);
         try {
             const hostName = escapeGqlString(name.trim());
+            const previous = await lastOperation('CreateLobby');
             await application.query(JSON.stringify({
                 query: `mutation { createLobby(hostName: "${hostName}") }`
             }));
+
+            // The room ID is assigned when the operation executes on-chain
+            const outcome = await lastOperation('CreateLobby');
+            if (!outcome || outcome.sequence === previous?.sequence) {
+                console.error('[Linera] Create lobby was not executed');
+                return;
+            }
+            if (outcome.error) {
+                console.error('[Linera] Create lobby failed:', outcome.error);
+                return;
+            }
+            setRoomId(outcome.response.roomId);
+            setIsHost(true);
             setScreen('waiting');
         } catch (e) {
             console.error('[Linera] Create lobby failed:', e);
@@ -87,16 +116,26 @@ export const GameLobby: React.FC<LobbyProps> = ({ onJoin }) => {
     };
 
     const handleJoinLobby = async () => {
-        if (!name.trim() || !hostChainId.trim() || !application || !ready) return;
+        if (!name.trim() || !roomId.trim() || !application || !ready) return;
         setIsProcessing(true);
         try {
             const playerName = escapeGqlString(name.trim());
-            const hostId = escapeGqlString(hostChainId.trim());
+            const joinedRoomId = roomId.trim();
+            const hostChainId = hostChainIdOf(joinedRoomId);
+            const previous = await lastOperation('JoinLobby');
             await application.query(JSON.stringify({
-                query: `mutation { joinLobby(hostChainId: "${hostId}", playerName: "${playerName}") }`
+                query: `mutation { joinLobby(hostChainId: "${escapeGqlString(hostChainId)}", roomId: "${escapeGqlString(joinedRoomId)}", playerName: "${playerName}") }`
             }));
-            // After joining, immediately go to game
-            onJoin(name.trim(), hostChainId.trim(), chainId || '', false);
+
+            const outcome = await lastOperation('JoinLobby');
+            if (outcome && outcome.sequence !== previous?.sequence && outcome.error) {
+                console.error('[Linera] Join lobby failed:', outcome.error);
+                return;
+            }
+            // Wait in the lobby for the ready check
+            setRoomId(joinedRoomId);
+            setIsHost(false);
+            setScreen('waiting');
         } catch (e) {
             console.error('[Linera] Join lobby failed:', e);
         } finally {
@@ -104,10 +143,10 @@ export const GameLobby: React.FC<LobbyProps> = ({ onJoin }) => {
         }
     };
 
-    const handleCopyChainId = async () => {
-        if (!chainId) return;
+    const handleCopyRoomId = async () => {
+        if (!roomId) return;
         try {
-            await navigator.clipboard.writeText(chainId);
+            await navigator.clipboard.writeText(roomId);
             setCopied(true);
             setTimeout(() => setCopied(false), 2000);
         } catch { }
@@ -127,21 +166,21 @@ export const GameLobby: React.FC<LobbyProps> = ({ onJoin }) => {
             return (
                 <div className="space-y-6">
                     <div className="text-center">
-                        <div className="text-cyan-300 text-sm mb-2">LOBBY CREATED</div>
-                        <div className="text-2xl text-white font-bold animate-pulse">Waiting for opponent...</div>
+                        <div className="text-cyan-300 text-sm mb-2">{isHost ? 'LOBBY CREATED' : 'LOBBY JOINED'}</div>
+                        <div className="text-2xl text-white font-bold animate-pulse">Waiting for players...</div>
                     </div>
 
                     <div className="bg-[#050b14]/50 border border-cyan-900/50 p-4 space-y-2">
-                        <div className="text-cyan-400/60 text-xs tracking-widest">YOUR CHAIN ID (share this)</div>
+                        <div className="text-cyan-400/60 text-xs tracking-widest">ROOM ID (share this)</div>
                         <div className="flex gap-2">
                             <input
                                 type="text"
-                                value={chainId || ''}
+                                value={roomId}
                                 readOnly
                                 className="flex-1 bg-transparent border-none text-cyan-100 text-xs outline-none truncate"
                             />
                             <button
-                                onClick={handleCopyChainId}
+                                onClick={handleCopyRoomId}
                                 className="px-3 py-1 bg-cyan-600/30 hover:bg-cyan-500/50 text-cyan-200 text-xs transition-colors"
                             >
                                 {copied ? '✓' : 'COPY'}
@@ -150,7 +189,7 @@ export const GameLobby: React.FC<LobbyProps> = ({ onJoin }) => {
                     </div>
 
                     <button
-                        onClick={() => setScreen('menu')}
+                        onClick={() => { setRoomId(''); setScreen('menu'); }}
                         className="w-full py-2 text-cyan-400/60 hover:text-cyan-300 text-sm transition-colors"
                     >
                         ← BACK
@@ -164,23 +203,23 @@ export const GameLobby: React.FC<LobbyProps> = ({ onJoin }) => {
                 <div className="space-y-6">
                     <div className="relative group">
                         <label className="text-cyan-300 text-xs tracking-widest mb-2 block uppercase opacity-70">
-                            Host Chain ID
+                            Room ID
                         </label>
                         <input
                             type="text"
-                            value={hostChainId}
-                            onChange={(e) => setHostChainId(e.target.value)}
-                            placeholder="PASTE HOST CHAIN ID..."
+                            value={roomId}
+                            onChange={(e) => setRoomId(e.target.value)}
+                            placeholder="PASTE ROOM ID..."
                             className="w-full bg-[#050b14]/50 border border-cyan-900/50 text-cyan-100 px-4 py-3 outline-none focus:border-cyan-400/80 transition-all placeholder:text-cyan-900/30 text-sm font-mono"
                         />
                     </div>
 
                     <button
                         onClick={handleJoinLobby}
-                        disabled={!name.trim() || !hostChainId.trim() || isProcessing}
+                        disabled={!name.trim() || !roomId.trim() || isProcessing}
                         className={`w-full py-4 bg-green-600/40 hover:bg-green-500/60 border-2 border-green-500/30 
                             text-white font-black tracking-widest transition-all
-                            ${(!name.trim() || !hostChainId.trim() || isProcessing) ? 'opacity-50 cursor-not-allowed' : ''}`}
+                            ${(!name.trim() || !roomId.trim() || isProcessing) ? 'opacity-50 cursor-not-allowed' : ''}`}
                     >
                         {isProcessing ? 'JOINING...' : 'JOIN BATTLE'}
                     </button>
@@ -6,19 +6,23 @@ interface LineraScores {
     redScore: number;
     currentRound: number;
     matchState: string;
-    bluePlayerName: string;
-    redPlayerName: string;
+    blueTeam: string[];
+    redTeam: string[];
 }
 
-export const LineraScoreDisplay: React.FC = () => {
+interface LineraScoreDisplayProps {
+    roomId: string;
+}
+
+export const LineraScoreDisplay: React.FC<LineraScoreDisplayProps> = ({ roomId }) => {
     const { client, application, ready } = useLinera();
     const [scores, setScores] = useState<LineraScores>({
         blueScore: 0,
         redScore: 0,
         currentRound: 0,
         matchState: 'WAITING',
-        bluePlayerName: '',
-        redPlayerName: ''
+        blueTeam: [],
+        redTeam: []
     });
 
     useEffect(() => {
@@ -37,10 +41,10 @@ export const LineraScoreDisplay: React.FC = () => {
             try {
                 const res = await application.query(JSON.stringify({
                     query: `query { 
-                        gameStatus { 
+                        gameStatus(roomId: ${JSON.stringify(roomId)}) { 
                             matchState 
-                            bluePlayerName 
-                            redPlayerName 
+                            blueTeam 
+                            redTeam 
                             blueScore 
                             redScore 
                             currentRound 
@@ -57,8 +61,8 @@ export const LineraScoreDisplay: React.FC = () => {
                         redScore: data.redScore ?? 0,
                         currentRound: data.currentRound ?? 0,
                         matchState: data.matchState ?? 'WAITING',
-                        bluePlayerName: data.bluePlayerName ?? '',
-                        redPlayerName: data.redPlayerName ?? ''
+                        blueTeam: data.blueTeam ?? [],
+                        redTeam: data.redTeam ?? []
                     });
                 }
             } catch (e) {
@@ -89,7 +93,7 @@ export const LineraScoreDisplay: React.FC = () => {
                 try { (client as any).offNotification?.(handleNotification); } catch { }
             }
         };
-    }, [client, application, ready]);
+    }, [client, application, ready, roomId]);
 
     if (!ready) return null;
 
@@ -111,7 +115,7 @@ export const LineraScoreDisplay: React.FC = () => {
                             {scores.blueScore}
                         </span>
                         <span className="text-blue-400/60 text-[10px] tracking-[0.3em] font-bold">
-                            {scores.bluePlayerName || 'BLUE'}
+                            {scores.blueTeam.join(' · ') || 'BLUE'}
                         </span>
                     </div>
 
@@ -129,7 +133,7 @@ export const LineraScoreDisplay: React.FC = () => {
                             {scores.redScore}
                         </span>
                         <span className="text-red-400/60 text-[10px] tracking-[0.3em] font-bold">
-                            {scores.redPlayerName || 'RED'}
+                            {scores.redTeam.join(' · ') || 'RED'}
                         </span>
                     </div>
                 </div>